@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mandelbrot::{
+    complex_matrix, escape_time, escape_times, get_members, is_stable, render, EscapeConfig,
+    Fractal, IterationPolicy, Lanes, RenderParams, DEFAULT_TILE_SIZE,
+};
+use num_complex::Complex;
+
+// Fixed inputs so numbers stay comparable between runs and branches.
+const ITERATIONS: usize = 100;
+const CONFIG: EscapeConfig = EscapeConfig::new(ITERATIONS);
+const DENSITY: i128 = 200;
+
+fn members(criterion: &mut Criterion) {
+    let c = complex_matrix(-2.0, 0.5, -1.5, 1.5, DENSITY).unwrap();
+    criterion.bench_function("get_members", |b| {
+        b.iter(|| get_members(black_box(&c), ITERATIONS, Fractal::Mandelbrot))
+    });
+    // Compare builds with and without `--features simd`.
+    let points = c.iter().copied().collect::<Vec<_>>();
+    criterion.bench_function("escape_times", |b| {
+        b.iter(|| escape_times(black_box(&points), &CONFIG, Lanes::Four))
+    });
+}
+
+fn renders(criterion: &mut Criterion) {
+    // A view dominated by boundary, where some rows cost far more than others.
+    let rows = RenderParams {
+        x_min: -0.8,
+        x_max: -0.7,
+        y_min: 0.05,
+        y_max: 0.15,
+        size: Some((256, 256)),
+        iterations: IterationPolicy::Fixed(500),
+        ..RenderParams::default()
+    };
+    let tiles = RenderParams {
+        tile_size: Some(DEFAULT_TILE_SIZE),
+        ..rows.clone()
+    };
+    criterion.bench_function("render/rows", |b| b.iter(|| render(black_box(&rows))));
+    criterion.bench_function("render/tiles", |b| b.iter(|| render(black_box(&tiles))));
+}
+
+fn points(criterion: &mut Criterion) {
+    // Interior but outside the cardioid and period-2 bulb, so it runs the full loop.
+    let interior = Complex::new(-0.1, 0.65);
+    let exterior = Complex::new(0.3, 0.5);
+    criterion.bench_function("is_stable/interior", |b| {
+        b.iter(|| is_stable(black_box(&interior), ITERATIONS))
+    });
+    criterion.bench_function("escape_time/exterior", |b| {
+        b.iter(|| escape_time(black_box(&exterior), &CONFIG))
+    });
+}
+
+criterion_group!(benches, members, renders, points);
+criterion_main!(benches);
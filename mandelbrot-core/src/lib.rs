@@ -0,0 +1,204 @@
+// The escape-time arithmetic on its own: no `std`, no allocation and no I/O,
+// so it runs on embedded targets. Float functions come from `libm`, through
+// `num-traits`. The `mandelbrot` crate builds its rendering on top of these
+// and re-exports them.
+//
+// The escape loop is generic over the scalar `T`, so `f32` can trade
+// accuracy for speed and memory; constants are given in `f64` and converted.
+#![no_std]
+
+use num_complex::Complex;
+use num_traits::Float;
+
+// Orbits escape once `norm_sqr()` exceeds the bailout; 4.0 is the escape
+// radius 2, the smallest that still decides membership correctly.
+pub const DEFAULT_BAILOUT: f64 = 4.0;
+
+// An orbit that returns within `PERIODICITY_EPSILON` of the reference point
+// is treated as periodic and therefore bounded. The reference is re-captured
+// every `PERIODICITY_CHECK_INTERVAL` iterations, so cycles up to that length
+// are caught.
+pub const PERIODICITY_EPSILON: f64 = 1e-10;
+pub const PERIODICITY_CHECK_INTERVAL: usize = 20;
+
+// The most extra steps an escaped orbit takes to reach
+// `EscapeConfig::smoothing_bailout_sqr`; far more than any orbit past the
+// escape radius needs.
+pub const SMOOTHING_STEPS: usize = 64;
+
+// The tunables shared by every escape-time function. Start from `new` and
+// change the rest with the `with_` methods; fields may be added, so the
+// struct can't be built literally outside this crate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct EscapeConfig {
+    pub max_iter: usize,
+    // Compared against `norm_sqr()`; see `DEFAULT_BAILOUT`.
+    pub bailout_sqr: f64,
+    // The exponent in `z^power + c`, at least 2. Formulas of a fixed degree,
+    // such as the Burning Ship, ignore it.
+    pub power: u32,
+    // Where every orbit starts. The cardioid shortcut only holds for 0, so
+    // any other start iterates every point in full.
+    pub z0: Complex<f64>,
+    // Compared against `norm_sqr()` like `bailout_sqr`, but only decides
+    // where the smooth count is taken, not membership. `None` takes it two
+    // steps past the escape instead.
+    pub smoothing_bailout_sqr: Option<f64>,
+}
+
+impl EscapeConfig {
+    // Quadratic, with `DEFAULT_BAILOUT`.
+    pub const fn new(max_iter: usize) -> Self {
+        EscapeConfig {
+            max_iter,
+            bailout_sqr: DEFAULT_BAILOUT,
+            power: 2,
+            z0: Complex::new(0.0, 0.0),
+            smoothing_bailout_sqr: None,
+        }
+    }
+
+    pub const fn with_bailout_sqr(self, bailout_sqr: f64) -> Self {
+        EscapeConfig {
+            bailout_sqr,
+            ..self
+        }
+    }
+
+    pub const fn with_power(self, power: u32) -> Self {
+        EscapeConfig { power, ..self }
+    }
+
+    pub const fn with_z0(self, z0: Complex<f64>) -> Self {
+        EscapeConfig { z0, ..self }
+    }
+
+    pub const fn with_smoothing_bailout_sqr(self, smoothing_bailout_sqr: f64) -> Self {
+        EscapeConfig {
+            smoothing_bailout_sqr: Some(smoothing_bailout_sqr),
+            ..self
+        }
+    }
+
+    // Whether `in_main_cardioid_or_bulb` may stand in for iterating.
+    pub fn cardioid_applies(&self) -> bool {
+        self.power == 2 && self.z0 == Complex::new(0.0, 0.0)
+    }
+}
+
+// `Some(n)` is the zero-based iteration whose result first had a `norm_sqr()`
+// above `config.bailout_sqr`, so escaped points always satisfy
+// `n < config.max_iter`.
+pub fn escape_time<T: Float>(c: &Complex<T>, config: &EscapeConfig) -> Option<usize> {
+    let power = config.power;
+    if config.cardioid_applies() && in_main_cardioid_or_bulb(c) {
+        return None;
+    }
+    let c = *c;
+    let z0 = Complex::new(scalar(config.z0.re), scalar(config.z0.im));
+    iterate(z0, config.max_iter, scalar(config.bailout_sqr), |z| {
+        step(z, c, power)
+    })
+    .map(|(n, _)| n)
+}
+
+pub fn is_stable<T: Float>(c: &Complex<T>, num_iterations: usize) -> bool {
+    escape_time(c, &EscapeConfig::new(num_iterations)).is_none()
+}
+
+// One iteration of `z^power + c`, squaring directly in the common case.
+pub fn step<T: Float>(z: Complex<T>, c: Complex<T>, power: u32) -> Complex<T> {
+    if power == 2 {
+        z * z + c
+    } else {
+        z.powu(power) + c
+    }
+}
+
+// Exact membership test for the two largest components of the quadratic
+// Mandelbrot set, which together cover most of its interior.
+pub fn in_main_cardioid_or_bulb<T: Float>(c: &Complex<T>) -> bool {
+    let quarter = scalar::<T>(0.25);
+    let x = c.re - quarter;
+    let y2 = c.im * c.im;
+    let q = x * x + y2;
+    if q * (q + x) <= quarter * y2 {
+        return true;
+    }
+    (c.re + T::one()) * (c.re + T::one()) + y2 <= scalar(0.0625)
+}
+
+// Carries an orbit that escaped at iteration `n` with `z` on to where the
+// smooth count is taken: two more steps, or with `smoothing_bailout_sqr` set,
+// until `norm_sqr()` exceeds it, which leaves the log-log formula almost
+// exact for a large radius. Returns the iteration and `z` reached.
+pub fn smoothing_escape<F>(
+    (mut n, mut z): (usize, Complex<f64>),
+    config: &EscapeConfig,
+    step: F,
+) -> (usize, Complex<f64>)
+where
+    F: Fn(Complex<f64>) -> Complex<f64>,
+{
+    let Some(bailout) = config.smoothing_bailout_sqr else {
+        for _ in 0..2 {
+            z = step(z);
+        }
+        return (n + 2, z);
+    };
+    for _ in 0..SMOOTHING_STEPS {
+        if escaped(z, bailout) {
+            break;
+        }
+        z = step(z);
+        n += 1;
+    }
+    (n, z)
+}
+
+// Applies `step` from `z0` until `norm_sqr()` exceeds `bailout`, returning the
+// escape iteration together with the first `z` past it. Orbits caught in a
+// cycle by the periodicity check, or still bounded after `max_iter` steps,
+// give `None`.
+pub fn iterate<T, F>(
+    z0: Complex<T>,
+    max_iter: usize,
+    bailout: T,
+    step: F,
+) -> Option<(usize, Complex<T>)>
+where
+    T: Float,
+    F: Fn(Complex<T>) -> Complex<T>,
+{
+    let epsilon = scalar::<T>(PERIODICITY_EPSILON);
+    let mut z = z0;
+    let mut reference = z0;
+    for n in 0..max_iter {
+        z = step(z);
+        if escaped(z, bailout) {
+            return Some((n, z));
+        }
+        if (z - reference).norm_sqr() < epsilon * epsilon {
+            return None;
+        }
+        if (n + 1) % PERIODICITY_CHECK_INTERVAL == 0 {
+            reference = z;
+        }
+    }
+    None
+}
+
+// Whether `z` lies outside the bailout radius. A `z` that overflowed to
+// infinity or NaN counts as outside too: at high powers or bailouts the orbit
+// can overflow before it passes the bailout, and comparisons with a NaN
+// `norm_sqr()` are always false, so it would otherwise never escape.
+pub fn escaped<T: Float>(z: Complex<T>, bailout: T) -> bool {
+    let norm = z.norm_sqr();
+    norm > bailout || !norm.is_finite()
+}
+
+// Every `Float` can hold an `f64` constant, if only approximately.
+fn scalar<T: Float>(value: f64) -> T {
+    T::from(value).unwrap()
+}
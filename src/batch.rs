@@ -0,0 +1,200 @@
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{escape_time, EscapeConfig};
+
+// Points handed to one worker at a time.
+const CHUNK: usize = 256;
+
+// How many points the `simd` escape loop advances together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lanes {
+    Two,
+    #[default]
+    Four,
+    Eight,
+}
+
+// The same results as calling `escape_time` on each point. With the `simd`
+// feature the quadratic loop from 0 runs `lanes` points at a time; without
+// it, or for higher powers or a nonzero `z0`, every point goes through the
+// scalar loop and `lanes` is ignored.
+pub fn escape_times(
+    points: &[Complex<f64>],
+    config: &EscapeConfig,
+    lanes: Lanes,
+) -> Vec<Option<usize>> {
+    let mut escapes = vec![None; points.len()];
+    let fill = |(out, points): (&mut [Option<usize>], &[Complex<f64>])| {
+        #[cfg(feature = "simd")]
+        if config.cardioid_applies() {
+            return fill_chunk(out, points, config, lanes);
+        }
+        fill_scalar(out, points, config)
+    };
+    #[cfg(not(feature = "simd"))]
+    let _ = lanes;
+    #[cfg(feature = "parallel")]
+    escapes
+        .par_chunks_mut(CHUNK)
+        .zip(points.par_chunks(CHUNK))
+        .for_each(fill);
+    #[cfg(not(feature = "parallel"))]
+    escapes
+        .chunks_mut(CHUNK)
+        .zip(points.chunks(CHUNK))
+        .for_each(fill);
+    escapes
+}
+
+// `escape_times` with the default `Lanes`, for any list of points, such as
+// random samples or the output of another tool, rather than a grid.
+pub fn escape_times_for(points: &[Complex<f64>], config: &EscapeConfig) -> Vec<Option<usize>> {
+    escape_times(points, config, Lanes::default())
+}
+
+fn fill_scalar(out: &mut [Option<usize>], points: &[Complex<f64>], config: &EscapeConfig) {
+    for (out, point) in out.iter_mut().zip(points) {
+        *out = escape_time(point, config);
+    }
+}
+
+// The quadratic loop only.
+#[cfg(feature = "simd")]
+fn fill_chunk(
+    out: &mut [Option<usize>],
+    points: &[Complex<f64>],
+    config: &EscapeConfig,
+    lanes: Lanes,
+) {
+    for (out, escape) in out.iter_mut().zip(simd_escapes(points, config, lanes)) {
+        *out = escape.map(|(n, _)| n);
+    }
+}
+
+// `Fractal::Mandelbrot.escape` of each of `points`, on the calling thread,
+// through the vector loop `lanes` points at a time. Only for configs where
+// `cardioid_applies`.
+#[cfg(feature = "simd")]
+pub(crate) fn simd_escapes(
+    points: &[Complex<f64>],
+    config: &EscapeConfig,
+    lanes: Lanes,
+) -> Vec<Option<(usize, Complex<f64>)>> {
+    use crate::in_main_cardioid_or_bulb;
+
+    let (max_iter, bailout) = (config.max_iter, config.bailout_sqr);
+    let mut escapes = vec![None; points.len()];
+    let out = &mut escapes[..];
+
+    // Points the cardioid test already settles would only hold their batch
+    // at `max_iter`, so just the rest go through the vector loop.
+    let pending = points
+        .iter()
+        .enumerate()
+        .filter(|(_, point)| !in_main_cardioid_or_bulb(point))
+        .map(|(k, _)| k)
+        .collect::<Vec<_>>();
+    let width = match lanes {
+        Lanes::Two => 2,
+        Lanes::Four => 4,
+        Lanes::Eight => 8,
+    };
+    for batch in pending.chunks(width) {
+        match lanes {
+            Lanes::Two => vector::escape::<wide::f64x2>(batch, points, out, max_iter, bailout),
+            Lanes::Four => vector::escape::<wide::f64x4>(batch, points, out, max_iter, bailout),
+            Lanes::Eight => vector::escape::<wide::f64x8>(batch, points, out, max_iter, bailout),
+        }
+    }
+    escapes
+}
+
+#[cfg(feature = "simd")]
+mod vector {
+    use num_complex::Complex;
+    use std::ops::{Add, Mul, Sub};
+
+    pub(super) trait Lane:
+        Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+    {
+        fn splat(value: f64) -> Self;
+        // Lane `k` holds `value(k)` for `k < len` and zero after that.
+        fn load(len: usize, value: impl Fn(usize) -> f64) -> Self;
+        // Bit `k` is set unless lane `k` of `self` is finite and at most
+        // `rhs`, matching `escaped`.
+        fn escaped_mask(self, rhs: Self) -> u32;
+        fn get(self, k: usize) -> f64;
+    }
+
+    macro_rules! lane {
+        ($ty:ty, $n:literal) => {
+            impl Lane for $ty {
+                fn splat(value: f64) -> Self {
+                    <$ty>::splat(value)
+                }
+
+                fn load(len: usize, value: impl Fn(usize) -> f64) -> Self {
+                    let mut lanes = [0.0; $n];
+                    for (k, lane) in lanes.iter_mut().enumerate().take(len) {
+                        *lane = value(k);
+                    }
+                    <$ty>::new(lanes)
+                }
+
+                fn escaped_mask(self, rhs: Self) -> u32 {
+                    let inside = self.simd_le(rhs) & self.simd_lt(<$ty>::splat(f64::INFINITY));
+                    !inside.to_bitmask()
+                }
+
+                fn get(self, k: usize) -> f64 {
+                    self.to_array()[k]
+                }
+            }
+        };
+    }
+
+    lane!(wide::f64x2, 2);
+    lane!(wide::f64x4, 4);
+    lane!(wide::f64x8, 8);
+
+    // Iterates `points[batch[k]]` together, one per lane, writing each result
+    // to `out[batch[k]]`. The operation order follows the scalar loop so escape
+    // iterations and the escaped `z` match it exactly. Unused lanes start at
+    // `c = 0`, which never escapes, and are masked out.
+    pub(super) fn escape<V: Lane>(
+        batch: &[usize],
+        points: &[Complex<f64>],
+        out: &mut [Option<(usize, Complex<f64>)>],
+        max_iter: usize,
+        bailout: f64,
+    ) {
+        let c_re = V::load(batch.len(), |k| points[batch[k]].re);
+        let c_im = V::load(batch.len(), |k| points[batch[k]].im);
+        let bailout = V::splat(bailout);
+        let live = (1u32 << batch.len()) - 1;
+
+        let mut escaped = 0;
+        let mut re = V::splat(0.0);
+        let mut im = V::splat(0.0);
+        for n in 0..max_iter {
+            let next_re = re * re - im * im + c_re;
+            im = re * im + im * re + c_im;
+            re = next_re;
+            let fresh = (re * re + im * im).escaped_mask(bailout) & live & !escaped;
+            if fresh == 0 {
+                continue;
+            }
+            for (k, &index) in batch.iter().enumerate() {
+                if fresh & (1 << k) != 0 {
+                    out[index] = Some((n, Complex::new(re.get(k), im.get(k))));
+                }
+            }
+            escaped |= fresh;
+            if escaped == live {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    gamma_table, in_main_cardioid_or_bulb, validate, with_threads, MandelbrotError, RenderParams,
+};
+use image::{Rgb, RgbImage};
+use ndarray::Array2;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Every escaping orbit starts inside this square, whatever part of the
+// plane is being viewed.
+const SAMPLE_RADIUS: f64 = 2.0;
+
+// Samples drawn from one generator. Block `k` is seeded from the run's seed
+// and `k` alone, so the result does not depend on how blocks are spread
+// across threads.
+const BLOCK: usize = 1 << 16;
+
+// Counts how often the orbits of `samples` random escaping points pass
+// through each pixel of the viewport in `params`. Row 0 is the top of the
+// image, matching `render`. Each worker fills its own histogram and the
+// histograms are summed at the end, so the same `params.seed` always gives
+// the same counts.
+pub fn buddhabrot_histogram(
+    samples: usize,
+    max_iter: usize,
+    params: &RenderParams,
+) -> Result<Array2<u32>, MandelbrotError> {
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    if width < 2 || height < 2 {
+        return Ok(Array2::zeros((height, width)));
+    }
+    let seed = params.seed.unwrap_or_else(fresh_seed);
+    let blocks = samples.div_ceil(BLOCK);
+    let block = |mut histogram: Array2<u32>, k: usize| {
+        let samples = BLOCK.min(samples - k * BLOCK);
+        let rng = StdRng::seed_from_u64(seed.wrapping_add(k as u64));
+        accumulate(&mut histogram, rng, samples, max_iter, params);
+        histogram
+    };
+    let zeros = || Array2::zeros((height, width));
+
+    with_threads(params.threads, || {
+        #[cfg(feature = "parallel")]
+        let histogram = (0..blocks)
+            .into_par_iter()
+            .fold(zeros, block)
+            .reduce(zeros, |a, b| a + b);
+        #[cfg(not(feature = "parallel"))]
+        let histogram = (0..blocks).fold(zeros(), block);
+        histogram
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn fresh_seed() -> u64 {
+    rand::random()
+}
+
+// There is no entropy source to assume on `wasm32-unknown-unknown`, so
+// unseeded runs there all share one seed.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn fresh_seed() -> u64 {
+    0
+}
+
+// Adds the orbits of `samples` points drawn from `rng` to `histogram`.
+fn accumulate(
+    histogram: &mut Array2<u32>,
+    mut rng: StdRng,
+    samples: usize,
+    max_iter: usize,
+    params: &RenderParams,
+) {
+    let (height, width) = histogram.dim();
+    let scale_x = (width - 1) as f64 / (params.x_max - params.x_min);
+    let scale_y = (height - 1) as f64 / (params.y_max - params.y_min);
+    let mut orbit = Vec::with_capacity(max_iter);
+    for _ in 0..samples {
+        let c = Complex::new(
+            rng.gen_range(-SAMPLE_RADIUS..SAMPLE_RADIUS),
+            rng.gen_range(-SAMPLE_RADIUS..SAMPLE_RADIUS),
+        );
+        if !escaping_orbit(&c, max_iter, &mut orbit) {
+            continue;
+        }
+        for z in &orbit {
+            let x = ((z.re - params.x_min) * scale_x).round();
+            let y = ((params.y_max - z.im) * scale_y).round();
+            if x >= 0.0 && y >= 0.0 && (x as usize) < width && (y as usize) < height {
+                histogram[[y as usize, x as usize]] += 1;
+            }
+        }
+    }
+}
+
+// Leaves the orbit of `c` in `orbit` and returns whether it escaped; only
+// escaping orbits contribute to the Buddhabrot.
+fn escaping_orbit(c: &Complex<f64>, max_iter: usize, orbit: &mut Vec<Complex<f64>>) -> bool {
+    orbit.clear();
+    if in_main_cardioid_or_bulb(c) {
+        return false;
+    }
+    let mut z = Complex::new(0.0, 0.0);
+    for _ in 0..max_iter {
+        z = z * z + c;
+        if z.norm_sqr() > 4.0 {
+            return true;
+        }
+        orbit.push(z);
+    }
+    false
+}
+
+// Brightness is the square root of the normalized hit count, which keeps the
+// faint outer wisps visible next to the dense core.
+pub fn render_buddhabrot(
+    samples: usize,
+    max_iter: usize,
+    params: &RenderParams,
+) -> Result<RgbImage, MandelbrotError> {
+    let histogram = buddhabrot_histogram(samples, max_iter, params)?;
+    let (height, width) = histogram.dim();
+    let brightness = brightness(&histogram, params);
+    Ok(RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let v = brightness(x, y);
+        Rgb([v, v, v])
+    }))
+}
+
+// The "Nebulabrot": three Buddhabrots of the same sample points, one per
+// channel, with the orbits capped at `max_iters` red, green and blue. Each
+// channel is normalized to its own densest pixel as in `render_buddhabrot`,
+// so the short-lived orbits of a low cap are as bright as the long ones.
+pub fn render_nebulabrot(
+    samples: usize,
+    max_iters: [usize; 3],
+    params: &RenderParams,
+) -> Result<RgbImage, MandelbrotError> {
+    let params = RenderParams {
+        seed: Some(params.seed.unwrap_or_else(fresh_seed)),
+        ..params.clone()
+    };
+    let mut channels = Vec::with_capacity(3);
+    for max_iter in max_iters {
+        channels.push(buddhabrot_histogram(samples, max_iter, &params)?);
+    }
+    let (height, width) = channels[0].dim();
+    let [r, g, b] = [0, 1, 2].map(|k| brightness(&channels[k], &params));
+    Ok(RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        Rgb([r(x, y), g(x, y), b(x, y)])
+    }))
+}
+
+// The byte each pixel of `histogram` is drawn with.
+fn brightness<'a>(
+    histogram: &'a Array2<u32>,
+    params: &RenderParams,
+) -> impl Fn(u32, u32) -> u8 + 'a {
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let gamma = gamma_table(params.gamma);
+    move |x, y| {
+        let hits = histogram[[y as usize, x as usize]] as f64;
+        let v = ((hits / max).sqrt() * 255.0).round() as u8;
+        gamma.map_or(v, |table| table[v as usize])
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    ColorMode, ColorScale, Dither, Fractal, InteriorMode, IterationPolicy, Palette, RenderParams,
+    Sampling, Viewport,
+};
+use num_complex::Complex;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+
+// Builds `RenderParams` one setting at a time, starting from the defaults,
+// which reproduce the CLI's default image.
+#[derive(Clone, Debug, Default)]
+pub struct RenderParamsBuilder {
+    params: RenderParams,
+}
+
+impl RenderParams {
+    pub fn builder() -> RenderParamsBuilder {
+        RenderParamsBuilder::default()
+    }
+}
+
+impl RenderParamsBuilder {
+    pub fn new() -> Self {
+        RenderParamsBuilder::default()
+    }
+
+    // Sets the bounds and the exact image size, replacing `density`.
+    pub fn viewport(self, viewport: Viewport) -> Self {
+        RenderParamsBuilder {
+            params: self.params.with_viewport(&viewport),
+        }
+    }
+
+    pub fn bounds(self, x_min: f64, x_max: f64, y_min: f64, y_max: f64) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                x_min,
+                x_max,
+                y_min,
+                y_max,
+                ..self.params
+            },
+        }
+    }
+
+    // A fixed limit; see `iteration_policy` for one that follows the zoom.
+    pub fn iterations(self, iterations: usize) -> Self {
+        self.iteration_policy(IterationPolicy::Fixed(iterations))
+    }
+
+    pub fn iteration_policy(self, iterations: IterationPolicy) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                iterations,
+                ..self.params
+            },
+        }
+    }
+
+    // Samples per unit of the complex plane; clears any size set by
+    // `viewport`.
+    pub fn density(self, pixel_density: i128) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                pixel_density,
+                size: None,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn sampling(self, sampling: Sampling) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                sampling,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn color_mode(self, color_mode: ColorMode) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                color_mode,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn color_scale(self, color_scale: ColorScale) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                color_scale,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn palette_offset(self, palette_offset: f64) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                palette_offset,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn palette(self, palette: Palette) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                palette,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn interior_mode(self, interior_mode: InteriorMode) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                interior_mode,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn interior_palette(self, interior_palette: Palette) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                interior_palette,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn gamma(self, gamma: f64) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                gamma,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn dither(self, dither: Dither) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                dither,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn interior_budget(self, budget: usize) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                interior_budget: Some(budget),
+                ..self.params
+            },
+        }
+    }
+
+    pub fn rotation(self, rotation: f64) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                rotation,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn fractal(self, fractal: Fractal) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                fractal,
+                ..self.params
+            },
+        }
+    }
+
+    // See `RenderParams::z0`.
+    pub fn z0(self, z0: Complex<f64>) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams { z0, ..self.params },
+        }
+    }
+
+    // See `RenderParams::tile_size`.
+    pub fn tile_size(self, tile_size: u32) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                tile_size: Some(tile_size),
+                ..self.params
+            },
+        }
+    }
+
+    // See `RenderParams::max_dimension`.
+    pub fn max_dimension(self, max_dimension: u32) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                max_dimension,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn allow_huge(self, allow_huge: bool) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                allow_huge,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn newton_shading(self, newton_shading: bool) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                newton_shading,
+                ..self.params
+            },
+        }
+    }
+
+    // See `RenderParams::output`.
+    #[cfg(feature = "fs")]
+    pub fn output(self, output: impl Into<PathBuf>) -> Self {
+        RenderParamsBuilder {
+            params: RenderParams {
+                output: Some(output.into()),
+                ..self.params
+            },
+        }
+    }
+
+    pub fn build(self) -> RenderParams {
+        self.params
+    }
+}
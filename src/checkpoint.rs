@@ -0,0 +1,163 @@
+use crate::metadata::fields;
+use crate::{validate, with_fractal_shader, with_threads, Dither, MandelbrotError, RenderParams};
+use image::RgbImage;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+// Bump the version whenever `header` or `metadata::fields` changes what the
+// settings text holds.
+const MAGIC: &[u8; 8] = b"MBCKPT03";
+
+// Rows rendered between checkpoint writes.
+const BAND_ROWS: usize = 32;
+
+// Renders like `render`, but appends every finished band of rows to the
+// file at `checkpoint`. If that file already exists, its rows are loaded and
+// only the missing ones are rendered, so an interrupted render picks up where
+// it stopped. The file is removed once the image is complete.
+//
+// The file starts with a header recording the image size and the settings
+// that affect pixels; resuming with different ones is an error rather than a
+// silently mixed image. A band cut off part-way through writing is ignored
+// and rendered again. Jittered and randomly dithered renders need a fixed
+// `seed`, since a fresh one each run would mix two different images.
+pub fn render_checkpointed(
+    params: &RenderParams,
+    checkpoint: &Path,
+) -> Result<RgbImage, MandelbrotError> {
+    validate(params)?;
+    if (params.jitter || params.dither == Dither::Random) && params.seed.is_none() {
+        return Err(MandelbrotError::Checkpoint(
+            "jittered and randomly dithered renders need a seed to resume".to_string(),
+        ));
+    }
+    let (width, height) = params.grid_size();
+    let header = header(params, width, height);
+    let stride = width * 3;
+
+    let mut pixels = vec![0u8; stride * height];
+    let mut done = vec![false; height];
+    let valid = if checkpoint.exists() {
+        load(checkpoint, &header, stride, &mut pixels, &mut done)?
+    } else {
+        None
+    };
+    match valid {
+        // Drop any half-written band so new ones start on a record boundary.
+        Some(valid) => OpenOptions::new()
+            .write(true)
+            .open(checkpoint)?
+            .set_len(valid)?,
+        None => fs::write(checkpoint, &header)?,
+    }
+
+    let mut file = OpenOptions::new().append(true).open(checkpoint)?;
+    with_threads(params.threads, || {
+        with_fractal_shader(params, params.fractal, |shader| {
+            for band in missing_bands(&done) {
+                let rows: RgbImage = shader.rows(band.clone(), &mut |_, _| {});
+                let mut record = Vec::with_capacity(8 + rows.len());
+                record.extend_from_slice(&(band.start as u32).to_le_bytes());
+                record.extend_from_slice(&(band.len() as u32).to_le_bytes());
+                record.extend_from_slice(&rows);
+                file.write_all(&record)?;
+                file.sync_data()?;
+                pixels[band.start * stride..band.end * stride].copy_from_slice(&rows);
+            }
+            Ok::<_, io::Error>(())
+        })
+    })??;
+    drop(file);
+    fs::remove_file(checkpoint)?;
+
+    Ok(RgbImage::from_raw(width as u32, height as u32, pixels)
+        .expect("buffer holds width * height pixels"))
+}
+
+// Everything that changes the rendered pixels, so a checkpoint is only
+// resumed by the render that wrote it: the `fields` pairs one per line.
+// Settings such as the thread count or the size limit are left out, so
+// changing them doesn't refuse a valid resume.
+pub(crate) fn header(params: &RenderParams, width: usize, height: usize) -> Vec<u8> {
+    let mut settings = String::new();
+    for (key, value) in fields(params) {
+        settings.push_str(&format!("{key}={value}\n"));
+    }
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&(width as u32).to_le_bytes());
+    header.extend_from_slice(&(height as u32).to_le_bytes());
+    header.extend_from_slice(&(settings.len() as u32).to_le_bytes());
+    header.extend_from_slice(settings.as_bytes());
+    header
+}
+
+// Fills in the rows stored in `checkpoint` and returns the length of its
+// complete records, or `None` if even the header is incomplete.
+fn load(
+    checkpoint: &Path,
+    header: &[u8],
+    stride: usize,
+    pixels: &mut [u8],
+    done: &mut [bool],
+) -> Result<Option<u64>, MandelbrotError> {
+    let mut reader = BufReader::new(File::open(checkpoint)?);
+    let mut found = vec![0u8; header.len()];
+    if !fill(&mut reader, &mut found)? {
+        return Ok(None);
+    }
+    if found != header {
+        return Err(MandelbrotError::Checkpoint(format!(
+            "{} was written by a render with different settings",
+            checkpoint.display()
+        )));
+    }
+    let mut valid = header.len() as u64;
+
+    let mut record = [0u8; 8];
+    while fill(&mut reader, &mut record)? {
+        let start = u32::from_le_bytes(record[..4].try_into().unwrap()) as usize;
+        let len = u32::from_le_bytes(record[4..].try_into().unwrap()) as usize;
+        if start + len > done.len() {
+            return Err(MandelbrotError::Checkpoint(format!(
+                "{} has rows past the end of the image",
+                checkpoint.display()
+            )));
+        }
+        let rows = &mut pixels[start * stride..(start + len) * stride];
+        if !fill(&mut reader, rows)? {
+            break;
+        }
+        done[start..start + len].fill(true);
+        valid += (record.len() + rows.len()) as u64;
+    }
+    Ok(Some(valid))
+}
+
+// `false` if the file ends before `buf` is full.
+fn fill(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+// Runs of rows that still need rendering, at most `BAND_ROWS` long.
+fn missing_bands(done: &[bool]) -> Vec<Range<usize>> {
+    let mut bands = Vec::new();
+    let mut y = 0;
+    while y < done.len() {
+        if done[y] {
+            y += 1;
+            continue;
+        }
+        let start = y;
+        while y < done.len() && !done[y] && y - start < BAND_ROWS {
+            y += 1;
+        }
+        bands.push(start..y);
+    }
+    bands
+}
@@ -0,0 +1,183 @@
+use num_complex::Complex;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum MandelbrotError {
+    Io(io::Error),
+    // The bounds must satisfy `x_min < x_max` and `y_min < y_max`.
+    InvalidViewport {
+        x_min: f64,
+        x_max: f64,
+        y_min: f64,
+        y_max: f64,
+    },
+    InvalidDensity(i128),
+    // `RenderParams::pixel_density` times the span of the viewport rounds to
+    // no samples along an axis; the fields are the unrounded counts.
+    DegenerateGrid {
+        width: f64,
+        height: f64,
+    },
+    // `RenderParams::size` has a zero side.
+    InvalidSize {
+        width: u32,
+        height: u32,
+    },
+    // The grid is larger than `RenderParams::max_dimension` along a side and
+    // `RenderParams::allow_huge` is not set.
+    DimensionsTooLarge {
+        width: usize,
+        height: usize,
+        limit: u32,
+    },
+    // `RenderParams::iterations` resolved to no iterations at all, or
+    // `RenderParams::interior_budget` allows none.
+    InvalidIterations(usize),
+    // `RenderParams::gamma` must be positive and finite.
+    InvalidGamma(f64),
+    // Zoom ranges must satisfy `0 < min_zoom < max_zoom` with both finite.
+    InvalidZoomRange {
+        min_zoom: f64,
+        max_zoom: f64,
+    },
+    // The tile lies outside the `2^zoom` x `2^zoom` grid.
+    InvalidTile {
+        zoom: u32,
+        tile_x: u32,
+        tile_y: u32,
+    },
+    Image(image::ImageError),
+    // A caller-supplied image or grid is not the size the render produces.
+    SizeMismatch {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    Plot(String),
+    // The output path's extension, possibly empty, names no format `save`
+    // can write.
+    UnsupportedFormat(String),
+    // A dedicated pool for `RenderParams::threads` could not be started.
+    ThreadPool(String),
+    // The PNG's `mandelbrot:` text chunks are missing or unreadable.
+    Metadata(String),
+    // A string passed to `RenderParams::from_str` is not in the form its
+    // `Display` writes, or `RenderParams::from_json` or `to_json` failed.
+    InvalidParams(String),
+    // A checkpoint file is corrupt or belongs to a different render.
+    Checkpoint(String),
+    // A gradient passed to `Palette::parse` or `Palette::from_file` is
+    // malformed.
+    InvalidPalette(String),
+    // `verify_grid` found image pixel `(x, y)`, sampled at `point`, counted
+    // as `member` of the set by the optimized render but not by the brute-force
+    // loop, or the other way round.
+    VerificationFailed {
+        x: u32,
+        y: u32,
+        point: Complex<f64>,
+        member: bool,
+    },
+}
+
+impl fmt::Display for MandelbrotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MandelbrotError::Io(err) => write!(f, "i/o error: {err}"),
+            MandelbrotError::InvalidViewport {
+                x_min,
+                x_max,
+                y_min,
+                y_max,
+            } => write!(
+                f,
+                "invalid viewport: x {x_min}..{x_max}, y {y_min}..{y_max} (each minimum must be below its maximum)"
+            ),
+            MandelbrotError::InvalidDensity(density) => {
+                write!(f, "pixel density must be positive, got {density}")
+            }
+            MandelbrotError::DegenerateGrid { width, height } => write!(
+                f,
+                "the viewport holds only {width}x{height} samples at this density; raise the density or set an explicit size"
+            ),
+            MandelbrotError::InvalidSize { width, height } => {
+                write!(f, "image size must be nonzero, got {width}x{height}")
+            }
+            MandelbrotError::DimensionsTooLarge {
+                width,
+                height,
+                limit,
+            } => write!(
+                f,
+                "a {width}x{height} image exceeds the {limit} pixel limit per side; lower the density or allow huge images"
+            ),
+            MandelbrotError::InvalidIterations(iterations) => {
+                write!(f, "iteration count must be positive, got {iterations}")
+            }
+            MandelbrotError::InvalidGamma(gamma) => {
+                write!(f, "gamma must be positive and finite, got {gamma}")
+            }
+            MandelbrotError::InvalidZoomRange { min_zoom, max_zoom } => write!(
+                f,
+                "zoom range {min_zoom}..{max_zoom} must be positive, finite and increasing"
+            ),
+            MandelbrotError::InvalidTile {
+                zoom,
+                tile_x,
+                tile_y,
+            } => write!(f, "tile ({tile_x}, {tile_y}) does not exist at zoom {zoom}"),
+            MandelbrotError::Image(err) => write!(f, "image encoding error: {err}"),
+            MandelbrotError::SizeMismatch { expected, found } => write!(
+                f,
+                "got {}x{} but the render is {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            MandelbrotError::Plot(message) => write!(f, "plotting error: {message}"),
+            MandelbrotError::UnsupportedFormat(extension) => write!(
+                f,
+                "unsupported output format `{extension}` (expected png, jpg, bmp or ppm)"
+            ),
+            MandelbrotError::ThreadPool(message) => {
+                write!(f, "could not start render threads: {message}")
+            }
+            MandelbrotError::Metadata(message) => write!(f, "png metadata error: {message}"),
+            MandelbrotError::InvalidParams(message) => {
+                write!(f, "invalid render parameters: {message}")
+            }
+            MandelbrotError::Checkpoint(message) => write!(f, "checkpoint error: {message}"),
+            MandelbrotError::InvalidPalette(message) => write!(f, "invalid palette: {message}"),
+            MandelbrotError::VerificationFailed {
+                x,
+                y,
+                point,
+                member,
+            } => write!(
+                f,
+                "pixel ({x}, {y}) at {point} is {} the set when optimized but not by brute force",
+                if *member { "in" } else { "outside" }
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MandelbrotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MandelbrotError::Io(err) => Some(err),
+            MandelbrotError::Image(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MandelbrotError {
+    fn from(err: io::Error) -> Self {
+        MandelbrotError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for MandelbrotError {
+    fn from(err: image::ImageError) -> Self {
+        MandelbrotError::Image(err)
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{validate, with_threads, MandelbrotError, RenderParams};
+use image::{ImageBuffer, Luma};
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub type Gray16Image = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+// Renders the escape iteration of every pixel as 16-bit gray, for analysis
+// where 8-bit palettes would band. Escape iteration `n` maps linearly to
+// `round(n * 65535 / iterations)` and points in the set to 65535, so black
+// is an immediate escape and white the interior. Coloring settings are
+// ignored, and the sampling is always one `f64` point per pixel.
+pub fn render_gray16(params: &RenderParams) -> Result<Gray16Image, MandelbrotError> {
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    let scale = u16::MAX as f64 / params.max_iter().max(1) as f64;
+    let config = params.escape_config();
+
+    let fill = |(y, row): (usize, &mut [u16])| {
+        let i = height - 1 - y;
+        for (j, pixel) in row.iter_mut().enumerate() {
+            let point = params.rotated(Complex::new(re[j], im[i]));
+            *pixel = match params.fractal.escape(&point, &config) {
+                Some((n, _)) => (n as f64 * scale).round() as u16,
+                None => u16::MAX,
+            };
+        }
+    };
+
+    let mut pixels = vec![0u16; width * height];
+    if pixels.is_empty() {
+        return Ok(ImageBuffer::new(width as u32, height as u32));
+    }
+    with_threads(params.threads, || {
+        #[cfg(feature = "parallel")]
+        pixels
+            .par_chunks_exact_mut(width)
+            .enumerate()
+            .for_each(fill);
+        #[cfg(not(feature = "parallel"))]
+        pixels.chunks_exact_mut(width).enumerate().for_each(fill);
+    })?;
+    Ok(ImageBuffer::from_raw(width as u32, height as u32, pixels)
+        .expect("buffer holds width * height pixels"))
+}
@@ -0,0 +1,35 @@
+use crate::{MandelbrotError, RenderParams};
+
+impl RenderParams {
+    // Every setting as a JSON object, field by field. Non-finite floats have
+    // no JSON form and come out as `null`, which `from_json` rejects.
+    pub fn to_json(&self) -> Result<String, MandelbrotError> {
+        serde_json::to_string(self).map_err(|err| MandelbrotError::InvalidParams(err.to_string()))
+    }
+
+    // The settings in a JSON object of the form `to_json` writes. Fields
+    // left out keep their `RenderParams::default()` values, so a preset only
+    // needs the ones it changes.
+    pub fn from_json(json: &str) -> Result<Self, MandelbrotError> {
+        serde_json::from_str(json).map_err(|err| MandelbrotError::InvalidParams(err.to_string()))
+    }
+}
+
+// `Rgb<u8>` as its `[r, g, b]` array, for `#[serde(with)]`.
+pub(crate) mod rgb {
+    use image::Rgb;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        color: &Rgb<u8>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        color.0.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Rgb<u8>, D::Error> {
+        <[u8; 3]>::deserialize(deserializer).map(Rgb)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{render_rgba_image, MandelbrotError, RenderParams};
+use image::{Rgb, RgbImage, RgbaImage};
+
+// One render in a `render_layers` stack. `opacity` scales the render's own
+// alpha, so an `Exterior::Transparent` layer only covers the set.
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub params: RenderParams,
+    pub opacity: f64,
+}
+
+impl Layer {
+    pub fn new(params: RenderParams, opacity: f64) -> Self {
+        Layer { params, opacity }
+    }
+}
+
+// Renders every layer as `render_rgba_image` does and blends them, first to
+// last, over black. All layers must come out the size of the first; any
+// other size is a `MandelbrotError::SizeMismatch`. An empty stack or an
+// opacity outside `[0, 1]` is refused before anything is rendered.
+pub fn render_layers(layers: &[Layer]) -> Result<RgbImage, MandelbrotError> {
+    let Some((first, rest)) = layers.split_first() else {
+        let message = "a layered render needs at least one layer".to_string();
+        return Err(MandelbrotError::InvalidParams(message));
+    };
+    if let Some(layer) = layers
+        .iter()
+        .find(|layer| !(0.0..=1.0).contains(&layer.opacity))
+    {
+        let message = format!(
+            "layer opacity must be between 0 and 1, got {}",
+            layer.opacity
+        );
+        return Err(MandelbrotError::InvalidParams(message));
+    }
+    let image = render_rgba_image(&first.params)?;
+    let mut out = RgbImage::new(image.width(), image.height());
+    blend_over(&mut out, &image, first.opacity);
+    for layer in rest {
+        let image = render_rgba_image(&layer.params)?;
+        if image.dimensions() != out.dimensions() {
+            return Err(MandelbrotError::SizeMismatch {
+                expected: out.dimensions(),
+                found: image.dimensions(),
+            });
+        }
+        blend_over(&mut out, &image, layer.opacity);
+    }
+    Ok(out)
+}
+
+// Blends `image`, its alpha scaled by `opacity`, over `out`.
+fn blend_over(out: &mut RgbImage, image: &RgbaImage, opacity: f64) {
+    for (below, above) in out.pixels_mut().zip(image.pixels()) {
+        let alpha = above[3] as f64 / 255.0 * opacity;
+        let blend = |b: u8, a: u8| (b as f64 + (a as f64 - b as f64) * alpha).round() as u8;
+        *below = Rgb([
+            blend(below[0], above[0]),
+            blend(below[1], above[1]),
+            blend(below[2], above[2]),
+        ]);
+    }
+}
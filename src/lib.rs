@@ -0,0 +1,4576 @@
+use image::{ImageBuffer, Pixel, Rgb, RgbImage, RgbaImage};
+use mandelbrot_core::{escaped, iterate, smoothing_escape, step};
+use mandelbrot_core::{PERIODICITY_CHECK_INTERVAL, PERIODICITY_EPSILON};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use ndarray::{linspace, Array, Array2};
+use num_complex::Complex;
+use num_traits::Float;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+use std::cell::Cell;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+mod batch;
+mod builder;
+#[cfg(feature = "fs")]
+mod checkpoint;
+mod buddhabrot;
+mod error;
+mod gray16;
+#[cfg(feature = "serde")]
+mod json;
+mod layers;
+mod limits;
+mod log_polar;
+mod marker;
+mod metadata;
+mod mipmap;
+mod outline;
+mod output;
+#[cfg(feature = "chart")]
+mod overlay;
+mod palette;
+#[cfg(feature = "chart")]
+mod plot;
+mod perturbation;
+mod precise;
+mod progressive;
+mod shading;
+#[cfg(feature = "chart")]
+mod sheet;
+mod stage;
+mod svg;
+mod tile;
+mod verify;
+mod viewport;
+#[cfg(feature = "fs")]
+mod zoom;
+
+pub use batch::{escape_times, escape_times_for, Lanes};
+pub use builder::RenderParamsBuilder;
+pub use buddhabrot::{buddhabrot_histogram, render_buddhabrot, render_nebulabrot};
+#[cfg(feature = "fs")]
+pub use checkpoint::render_checkpointed;
+pub use error::MandelbrotError;
+pub use gray16::{render_gray16, Gray16Image};
+pub use layers::{render_layers, Layer};
+pub use limits::{escape_grid_with_limits, render_with_iteration_limits};
+pub use log_polar::render_log_polar;
+pub use marker::{draw_markers, Marker, MarkerShape};
+pub use mandelbrot_core::{
+    escape_time, in_main_cardioid_or_bulb, is_stable, EscapeConfig, DEFAULT_BAILOUT,
+};
+#[cfg(feature = "fs")]
+pub use metadata::read_params;
+pub use mipmap::render_with_mipmaps;
+pub use outline::render_outline;
+pub use output::{
+    encode_png, encode_png_with_params, encode_ppm, render_png_bytes, render_to_writer,
+    write_with_params, OutputFormat, JPEG_QUALITY,
+};
+#[cfg(feature = "fs")]
+pub use output::{
+    path_error, prepare_output, save, save_as, save_with_params, write_npy, write_ppm,
+};
+#[cfg(feature = "chart")]
+pub use overlay::{draw_grid, with_legend, GridOverlay, Legend};
+pub use palette::{ColorSpace, Palette};
+#[cfg(feature = "chart")]
+pub use plot::{plot_mandelbrot, PlotCaption, PlotStyle, PLOT_CAPTION_SIZE};
+pub use precise::{from_fixed, precise_escape_time, to_fixed, Precision};
+pub use progressive::render_progressive;
+pub use shading::render_with_shading;
+#[cfg(feature = "chart")]
+pub use sheet::{render_grid, SHEET_GAP};
+pub use stage::{
+    apply_stages, render_with_stages, Gamma, OrderedDither, PixelContext, PixelStage, Tint,
+};
+pub use svg::{render_svg, trace_boundary};
+pub use tile::{render_tile, tile_bounds, TILE_BOUNDS};
+pub use verify::verify_grid;
+pub use viewport::{
+    axis_coordinates, complex_to_pixel, find_interesting_point, keyframe_viewports,
+    pixel_to_complex, suggest_max_iter, Easing, Keyframe, Viewport, ZOOM_1_HEIGHT,
+};
+#[cfg(feature = "fs")]
+pub use zoom::{
+    render_keyframes, render_zoom_sequence, render_zoom_sequence_cached, ZoomCacheStats,
+};
+
+pub struct Z {
+    z: Complex<f64>,
+    c: Complex<f64>,
+    power: u32,
+    limit: Option<u128>,
+    count: u128,
+}
+
+impl Iterator for Z {
+    type Item = Complex<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let r = self.z;
+        if let Some(limit) = self.limit {
+            if self.count >= limit {
+                return None;
+            }
+        }
+
+        self.z = step(self.z, self.c, self.power);
+
+        self.count += 1;
+
+        Some(r)
+    }
+}
+
+// Iterating yields the starting `z` followed by each successive iterate,
+// computed in `f64` with the same step the renderer uses.
+// With `limit(n)` exactly `n` values are produced (the starting `z` counts as
+// the first); without a limit the iterator never ends.
+#[derive(Default)]
+pub struct ZBuilder {
+    z: Complex<f64>,
+    candidate: Complex<f64>,
+    power: Option<u32>,
+    limit: Option<u128>,
+}
+
+impl ZBuilder {
+    pub fn new() -> Self {
+        ZBuilder::default()
+    }
+
+    pub fn z(self, z: Complex<f64>) -> Self {
+        ZBuilder {
+            z,
+            candidate: self.candidate,
+            power: self.power,
+            limit: self.limit,
+        }
+    }
+
+    pub fn c(self, c: Complex<f64>) -> Self {
+        ZBuilder {
+            z: self.z,
+            candidate: c,
+            power: self.power,
+            limit: self.limit,
+        }
+    }
+
+    // Powers below 2 are refused as `validate` refuses them in a render.
+    pub fn power(self, power: u32) -> Result<Self, MandelbrotError> {
+        check_power(power)?;
+        Ok(ZBuilder {
+            z: self.z,
+            candidate: self.candidate,
+            power: Some(power),
+            limit: self.limit,
+        })
+    }
+
+    pub fn limit(self, limit: u128) -> Self {
+        ZBuilder {
+            z: self.z,
+            candidate: self.candidate,
+            power: self.power,
+            limit: Some(limit),
+        }
+    }
+
+    pub fn build(self) -> Z {
+        Z {
+            z: self.z,
+            c: self.candidate,
+            power: self.power.unwrap_or(2),
+            limit: self.limit,
+            count: 0,
+        }
+    }
+}
+
+pub fn mandelbrot(c: Complex<f64>) -> ZBuilder {
+    ZBuilder::new().z(Complex::new(0.0, 0.0)).c(c)
+}
+
+// The orbit of the starting point `c` under `z^2 + p`.
+pub fn julia(c: Complex<f64>, p: Complex<f64>) -> ZBuilder {
+    ZBuilder::new().z(c).c(p)
+}
+
+// Generic over the scalar so that an `f32` grid, at half the memory, can feed
+// quick previews through `escape_time`; the sizes are worked out in `f64`, so
+// both precisions give grids of the same shape. Both sample counts follow
+// from `pixel_density`; `complex_matrix_with_samples` takes them directly.
+pub fn complex_matrix<T: Float>(
+    x_min: T,
+    x_max: T,
+    y_min: T,
+    y_max: T,
+    pixel_density: i128,
+) -> Result<Array2<Complex<T>>, MandelbrotError> {
+    let zero = T::zero();
+    complex_matrix_with_offset(x_min, x_max, y_min, y_max, pixel_density, (zero, zero))
+}
+
+// `complex_matrix` with every sample moved by `(dx, dy)` times the spacing
+// between samples, so fractions of a pixel line up grids from different
+// renders. The grid keeps its shape, an axis of a single sample has no
+// spacing and stays put, and a zero offset gives exactly the samples of
+// `complex_matrix`.
+pub fn complex_matrix_with_offset<T: Float>(
+    x_min: T,
+    x_max: T,
+    y_min: T,
+    y_max: T,
+    pixel_density: i128,
+    (dx, dy): (T, T),
+) -> Result<Array2<Complex<T>>, MandelbrotError> {
+    let wide = |v: T| v.to_f64().unwrap_or(f64::NAN);
+    let (x0, x1, y0, y1) = (wide(x_min), wide(x_max), wide(y_min), wide(y_max));
+    validate_bounds(x0, x1, y0, y1, pixel_density)?;
+    let shifted = |min: T, max: T, n: usize, offset: T| {
+        let axis = linspace(min, max, n);
+        if offset == T::zero() || n < 2 {
+            return axis.collect::<Vec<T>>();
+        }
+        let step = (max - min) / T::from(n - 1).unwrap_or_else(T::nan);
+        axis.map(|v| v + offset * step).collect()
+    };
+    let re = shifted(x_min, x_max, axis_len(x0, x1, pixel_density), dx);
+    let im = shifted(y_min, y_max, axis_len(y0, y1, pixel_density), dy);
+    Ok(grid(re, im))
+}
+
+// Exactly `width` x `height` samples of `viewport`, `(x_min, x_max, y_min,
+// y_max)`, laid out like `complex_matrix`, whatever the span: the output size
+// is chosen directly instead of following from a density, and wide or tall
+// windows are stretched to fit rather than changing the shape.
+pub fn complex_matrix_for_resolution<T: Float>(
+    viewport: (T, T, T, T),
+    width: usize,
+    height: usize,
+) -> Result<Array2<Complex<T>>, MandelbrotError> {
+    let (x_min, x_max, y_min, y_max) = viewport;
+    complex_matrix_with_samples(x_min, x_max, y_min, y_max, width, height)
+}
+
+// `complex_matrix` with `x_samples` columns and `y_samples` rows, both
+// ends of each axis included, so the spacing along the two axes can differ
+// on purpose, as for displays with non-square pixels.
+pub fn complex_matrix_with_samples<T: Float>(
+    x_min: T,
+    x_max: T,
+    y_min: T,
+    y_max: T,
+    x_samples: usize,
+    y_samples: usize,
+) -> Result<Array2<Complex<T>>, MandelbrotError> {
+    let wide = |v: T| v.to_f64().unwrap_or(f64::NAN);
+    validate_bounds(wide(x_min), wide(x_max), wide(y_min), wide(y_max), 1)?;
+    if x_samples == 0 || y_samples == 0 {
+        return Err(MandelbrotError::InvalidSize {
+            width: x_samples as u32,
+            height: y_samples as u32,
+        });
+    }
+    let re = linspace(x_min, x_max, x_samples).collect();
+    let im = linspace(y_min, y_max, y_samples).collect();
+    Ok(grid(re, im))
+}
+
+// Every `(re, im)` pair, one row per imaginary coordinate.
+fn grid<T: Float>(re: Vec<T>, im: Vec<T>) -> Array2<Complex<T>> {
+    let re_array = Array::from_vec(re);
+    let im_array = Array::from_vec(im);
+
+    let zero = Complex::new(T::zero(), T::zero());
+    let mut complex_array = Array2::from_elem((im_array.len(), re_array.len()), zero);
+
+    for (i, im_val) in im_array.iter().enumerate() {
+        for (j, re_val) in re_array.iter().enumerate() {
+            complex_array[[i, j]] = Complex::new(*re_val, *im_val);
+        }
+    }
+
+    complex_array
+}
+
+// Samples along one axis: the span times the density, rounded to the nearest
+// integer so spans like `0.3 - 0.2` that land just below a whole number are
+// not cut short. Every axis gets at least one sample, though renders refuse
+// spans that round to none; see `validate_samples`.
+fn axis_len(min: f64, max: f64, pixel_density: i128) -> usize {
+    (((max - min) * pixel_density as f64).round() as usize).max(1)
+}
+
+// The estimator is only accurate once |z| is large, so it iterates well past
+// the bailout used for escape times.
+const DISTANCE_BAILOUT: f64 = 1e20;
+
+// An estimate of the distance from `c` to the Mandelbrot set, within a
+// factor of about 2 of the true distance, from the derivative `dz/dc` carried
+// along the orbit. `None` for points that don't escape within `max_iter`.
+// The bailout is always `DISTANCE_BAILOUT`.
+pub fn distance_estimate(c: &Complex<f64>, config: &EscapeConfig) -> Option<f64> {
+    Fractal::Mandelbrot.distance_estimate(c, config)
+}
+
+// The orbit `0, c, c^2 + c, ...` of `c`, ending either with the first value
+// whose `norm_sqr()` exceeds the bailout or after `max_iter` steps, so it
+// holds at most `max_iter + 1` values. Periodic orbits are not cut short.
+pub fn orbit(c: Complex<f64>, config: &EscapeConfig) -> Vec<Complex<f64>> {
+    let mut z = config.z0;
+    let mut orbit = vec![z];
+    for _ in 0..config.max_iter {
+        z = step(z, c, config.power);
+        orbit.push(z);
+        if escaped(z, config.bailout_sqr) {
+            break;
+        }
+    }
+    orbit
+}
+
+// `z = z^power + c`; `power = 2` is the ordinary Mandelbrot set.
+pub fn multibrot_escape_time(c: &Complex<f64>, config: &EscapeConfig) -> Option<usize> {
+    Fractal::Mandelbrot.escape_time(c, config)
+}
+
+pub fn julia_escape_time(
+    z0: Complex<f64>,
+    c: Complex<f64>,
+    config: &EscapeConfig,
+) -> Option<usize> {
+    Fractal::Julia(c).escape_time(&z0, config)
+}
+
+// `z = (|re z| + i|im z|)^2 + c`
+pub fn burning_ship_escape_time(c: &Complex<f64>, config: &EscapeConfig) -> Option<usize> {
+    Fractal::BurningShip.escape_time(c, config)
+}
+
+// `z = conj(z)^2 + c`, the Tricorn or Mandelbar set.
+pub fn tricorn_escape_time(c: &Complex<f64>, config: &EscapeConfig) -> Option<usize> {
+    Fractal::Tricorn.escape_time(c, config)
+}
+
+// Far looser than `PERIODICITY_EPSILON`, but still far below the spacing of
+// points on a cycle, so it only matches a true sub-cycle.
+const PERIOD_TOLERANCE: f64 = 1e-6;
+
+// `z - f(z) / f'(z)` for `f(z) = z^3 - 1`.
+fn newton_step(z: Complex<f64>) -> Complex<f64> {
+    z - (z * z * z - 1.0) / (z * z * 3.0)
+}
+
+// The iteration at which Newton's method from `z` comes within
+// `NEWTON_TOLERANCE` of a root, and the index of that root in
+// `NEWTON_ROOTS`. Points that haven't settled within `max_iter` steps, such
+// as those on the basin boundaries, give `None`.
+fn newton_root(mut z: Complex<f64>, max_iter: usize) -> Option<(usize, usize)> {
+    for n in 0..max_iter {
+        z = newton_step(z);
+        let root = NEWTON_ROOTS
+            .iter()
+            .position(|root| (z - root).norm_sqr() < NEWTON_TOLERANCE * NEWTON_TOLERANCE);
+        if let Some(root) = root {
+            return Some((n, root));
+        }
+    }
+    None
+}
+
+// Fractional iteration count `n + 1 - log_d(ln|z|)` for escaped points, with
+// `d` the power, which varies continuously across escape bands. It is taken
+// past the escape as `smoothing_escape` says to shrink the error of the
+// approximation. Points that never escape return `max_iter` as a sentinel.
+pub fn smooth_escape_time(c: &Complex<f64>, config: &EscapeConfig) -> f64 {
+    Fractal::Mandelbrot.smooth_escape_time(c, config)
+}
+
+// Why `escape_time` decides as it does for a point; for checking the
+// shortcuts against the plain loop rather than for rendering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Classification {
+    // In the main cardioid or the period-2 bulb, so never iterated.
+    InsideCardioid,
+    // The periodicity check caught the orbit in a cycle of this length.
+    PeriodicStable(usize),
+    // Still bounded after every iteration allowed.
+    ReachedMaxIter,
+    // Escaped at this zero-based iteration, as `escape_time` reports it.
+    Escaped(usize),
+}
+
+impl Classification {
+    // Whether `is_stable` holds for the point.
+    pub fn is_stable(&self) -> bool {
+        !matches!(self, Classification::Escaped(_))
+    }
+}
+
+pub fn classify(c: &Complex<f64>, config: &EscapeConfig) -> Classification {
+    if config.cardioid_applies() && in_main_cardioid_or_bulb(c) {
+        return Classification::InsideCardioid;
+    }
+    Fractal::Mandelbrot.fate(c, config)
+}
+
+#[cfg(feature = "parallel")]
+pub fn get_members(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+) -> Vec<(f64, f64)> {
+    let config = EscapeConfig::new(num_iterations);
+    c.par_iter()
+        .filter(|value| fractal.escape_time(value, &config).is_none())
+        .map(|value| (value.re, value.im))
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn get_members(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+) -> Vec<(f64, f64)> {
+    let config = EscapeConfig::new(num_iterations);
+    let mut members = Vec::new();
+
+    for &value in c.iter() {
+        if fractal.escape_time(&value, &config).is_none() {
+            members.push((value.re, value.im));
+        }
+    }
+
+    members
+}
+
+// The members of the `complex_matrix` grid for the same arguments, in the
+// order `get_members` finds them, tested one at a time as the iterator is
+// advanced; only the two axes are ever held in memory.
+pub struct MemberIter {
+    re: Vec<f64>,
+    im: Vec<f64>,
+    // The next grid point to test.
+    i: usize,
+    j: usize,
+    config: EscapeConfig,
+    fractal: Fractal,
+}
+
+impl MemberIter {
+    pub fn new(
+        x_min: f64,
+        x_max: f64,
+        y_min: f64,
+        y_max: f64,
+        pixel_density: i128,
+        num_iterations: usize,
+        fractal: Fractal,
+    ) -> Result<Self, MandelbrotError> {
+        validate_bounds(x_min, x_max, y_min, y_max, pixel_density)?;
+        Ok(MemberIter {
+            re: linspace(x_min, x_max, axis_len(x_min, x_max, pixel_density)).collect(),
+            im: linspace(y_min, y_max, axis_len(y_min, y_max, pixel_density)).collect(),
+            i: 0,
+            j: 0,
+            config: EscapeConfig::new(num_iterations),
+            fractal,
+        })
+    }
+}
+
+impl Iterator for MemberIter {
+    type Item = (f64, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.i < self.im.len() {
+            let point = Complex::new(self.re[self.j], self.im[self.i]);
+            self.j += 1;
+            if self.j == self.re.len() {
+                self.j = 0;
+                self.i += 1;
+            }
+            if self.fractal.escape_time(&point, &self.config).is_none() {
+                return Some((point.re, point.im));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.im.len() - self.i) * self.re.len() - self.j;
+        (0, Some(remaining))
+    }
+}
+
+// The escape time of every point of `c`, laid out like `c` itself, so it can
+// be turned back into an image without re-deriving the grid.
+#[cfg(feature = "parallel")]
+pub fn escape_grid(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+) -> Array2<Option<usize>> {
+    let config = EscapeConfig::new(num_iterations);
+    ndarray::Zip::from(c).par_map_collect(|value| fractal.escape_time(value, &config))
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn escape_grid(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+) -> Array2<Option<usize>> {
+    let config = EscapeConfig::new(num_iterations);
+    c.map(|value| fractal.escape_time(value, &config))
+}
+
+// `escape_grid` for the Mandelbrot set with the ambiguous points checked
+// again in fixed point: only those `classify` leaves at
+// `ReachedMaxIter`, neither in the cardioid nor caught in a cycle, are
+// iterated a second time with `precise_escape_time` at `bits` fractional
+// bits, and that verdict is kept. The rest cost no more than in
+// `escape_grid`.
+pub fn escape_time_grid(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    bits: u32,
+) -> Array2<Option<usize>> {
+    let config = EscapeConfig::new(num_iterations);
+    let escape = |value: &Complex<f64>| match classify(value, &config) {
+        Classification::Escaped(n) => Some(n),
+        Classification::ReachedMaxIter => {
+            let (re, im) = (to_fixed(value.re, bits), to_fixed(value.im, bits));
+            precise_escape_time(&re, &im, num_iterations, bits)
+        }
+        _ => None,
+    };
+    #[cfg(feature = "parallel")]
+    return ndarray::Zip::from(c).par_map_collect(escape);
+    #[cfg(not(feature = "parallel"))]
+    c.map(escape)
+}
+
+// What `escape_value_grid` stores for an escaped point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridValue {
+    // The fractional count of `Fractal::smooth_escape_time`.
+    #[default]
+    Smooth,
+    // The whole escape iteration of `escape_grid`.
+    Count,
+}
+
+// The escape of every point of `c`, laid out like `c`, as one `f64` each:
+// `value` for the points that escape and `f64::INFINITY` for those in the
+// set, so membership is `is_infinite()`. Escape and smooth count come from a
+// single iteration of each point.
+pub fn escape_value_grid(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+    value: GridValue,
+) -> Array2<f64> {
+    let config = EscapeConfig::new(num_iterations);
+    let escape = |point: &Complex<f64>| match (fractal.escape(point, &config), value) {
+        (None, _) => f64::INFINITY,
+        (Some((n, _)), GridValue::Count) => n as f64,
+        (Some(escape), GridValue::Smooth) => fractal.smooth_value(point, escape, &config),
+    };
+    #[cfg(feature = "parallel")]
+    return ndarray::Zip::from(c).par_map_collect(escape);
+    #[cfg(not(feature = "parallel"))]
+    c.map(escape)
+}
+
+// `true` where the point of `c` is in the set.
+pub fn membership_grid(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+) -> Array2<bool> {
+    escape_grid(c, num_iterations, fractal).map(Option::is_none)
+}
+
+// The outline of a `membership_grid`: `true` for members with a non-member
+// among their four neighbours, so the line is one cell wide and runs just
+// inside the set. Cells beyond the edge of the grid don't count as
+// non-members.
+pub fn boundary_grid(grid: &Array2<bool>) -> Array2<bool> {
+    let (height, width) = grid.dim();
+    let outside = |i: usize, j: usize| !grid[[i, j]];
+    Array2::from_shape_fn((height, width), |(i, j)| {
+        grid[[i, j]]
+            && ((i > 0 && outside(i - 1, j))
+                || (i + 1 < height && outside(i + 1, j))
+                || (j > 0 && outside(i, j - 1))
+                || (j + 1 < width && outside(i, j + 1)))
+    })
+}
+
+// The area of the set inside `viewport`, `(x_min, x_max, y_min, y_max)`, as
+// the fraction of `grid` that is in the set times the viewport's area.
+// `grid` is a `membership_grid` over that viewport; an empty grid gives 0.
+// The iteration limit lets some exterior points count as members, so the
+// estimate errs high; the full set's area is about 1.506.
+pub fn estimate_area(grid: &Array2<bool>, viewport: (f64, f64, f64, f64)) -> f64 {
+    let (x_min, x_max, y_min, y_max) = viewport;
+    if grid.is_empty() {
+        return 0.0;
+    }
+    let members = grid.iter().filter(|&&member| member).count();
+    members as f64 / grid.len() as f64 * (x_max - x_min) * (y_max - y_min)
+}
+
+// The centre of mass of the members of `grid`, a `membership_grid` over
+// `viewport`, `(x_min, x_max, y_min, y_max)`, with every member pixel
+// weighted equally. A grid with no members gives the centre of the viewport.
+pub fn set_centroid(grid: &Array2<bool>, viewport: (f64, f64, f64, f64)) -> Complex<f64> {
+    let (x_min, x_max, y_min, y_max) = viewport;
+    let (rows, cols) = grid.dim();
+    let (re, im) = axis_coordinates(viewport, cols, rows);
+    let (mut sum, mut members) = (Complex::new(0.0, 0.0), 0usize);
+    for ((i, j), _) in grid.indexed_iter().filter(|(_, &member)| member) {
+        sum += Complex::new(re[j], im[i]);
+        members += 1;
+    }
+    match members {
+        0 => Complex::new((x_min + x_max) / 2.0, (y_min + y_max) / 2.0),
+        _ => sum / members as f64,
+    }
+}
+
+// The `set_centroid` of the render `params` describes: the centre of mass of
+// the sampled points that are in `params.fractal`, checked like a render and
+// rotated like one.
+pub fn render_centroid(params: &RenderParams) -> Result<Complex<f64>, MandelbrotError> {
+    let members = outline::membership(params)?;
+    let (height, width) = members.dim();
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    let (mut sum, mut count) = (Complex::new(0.0, 0.0), 0usize);
+    for ((i, j), _) in members.indexed_iter().filter(|(_, &member)| member) {
+        sum += params.rotated(Complex::new(re[j], im[i]));
+        count += 1;
+    }
+    Ok(match count {
+        0 => Complex::new((params.x_min + params.x_max) / 2.0, (params.y_min + params.y_max) / 2.0),
+        _ => sum / count as f64,
+    })
+}
+
+// `escape_grid` as plain counts for `write_npy`, with `num_iterations`
+// standing in for points in the set.
+pub fn escape_counts(
+    c: &Array2<Complex<f64>>,
+    num_iterations: usize,
+    fractal: Fractal,
+) -> Array2<u32> {
+    escape_grid(c, num_iterations, fractal).map(|escape| escape.unwrap_or(num_iterations) as u32)
+}
+
+// `(x_min, x_max, y_min, y_max)` of the classic full view of the Mandelbrot
+// set, used by `RenderParams::default()` and the CLI.
+pub const MANDELBROT_BOUNDS: (f64, f64, f64, f64) = (-2.0, 0.5, -1.5, 1.5);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fractal {
+    #[default]
+    Mandelbrot,
+    // The fixed `c`; each sampled point is the starting `z`.
+    Julia(Complex<f64>),
+    BurningShip,
+    // Conjugates `z` before squaring, which gives three-fold symmetry.
+    Tricorn,
+    // Newton's method for `z^3 - 1` from each sampled point: a point
+    // "escapes" once it lands within `NEWTON_TOLERANCE` of one of
+    // `NEWTON_ROOTS` and is colored by that root rather than by `palette`.
+    // `power` and `bailout` are ignored.
+    Newton,
+}
+
+// The cube roots of unity that `Fractal::Newton` converges to, and the color
+// of the points that reach each one.
+pub const NEWTON_ROOTS: [Complex<f64>; 3] = [
+    Complex::new(1.0, 0.0),
+    Complex::new(-0.5, 0.866_025_403_784_438_6),
+    Complex::new(-0.5, -0.866_025_403_784_438_6),
+];
+pub const NEWTON_COLORS: [Rgb<u8>; 3] =
+    [Rgb([220, 50, 47]), Rgb([133, 153, 0]), Rgb([38, 139, 210])];
+pub const NEWTON_TOLERANCE: f64 = 1e-6;
+
+impl Fractal {
+    // `(x_min, x_max, y_min, y_max)` framing the interesting part of the set.
+    pub fn default_bounds(&self) -> (f64, f64, f64, f64) {
+        match self {
+            Fractal::Mandelbrot => MANDELBROT_BOUNDS,
+            Fractal::Julia(_) => (-1.5, 1.5, -1.0, 1.0),
+            Fractal::BurningShip => (-2.0, 1.5, -2.0, 1.0),
+            Fractal::Tricorn => (-2.25, 1.75, -1.5, 1.5),
+            Fractal::Newton => (-2.0, 2.0, -1.5, 1.5),
+        }
+    }
+
+    // `config.power` applies to the Mandelbrot and Julia formulas; the
+    // Burning Ship and the Tricorn are always quadratic.
+    pub fn escape_time(&self, point: &Complex<f64>, config: &EscapeConfig) -> Option<usize> {
+        self.escape(point, config).map(|(n, _)| n)
+    }
+
+    // Same sentinel convention as the free `smooth_escape_time`.
+    pub fn smooth_escape_time(&self, point: &Complex<f64>, config: &EscapeConfig) -> f64 {
+        match self.escape(point, config) {
+            Some(escape) => self.smooth_value(point, escape, config),
+            None => config.max_iter as f64,
+        }
+    }
+
+    fn escape(&self, point: &Complex<f64>, config: &EscapeConfig) -> Option<(usize, Complex<f64>)> {
+        let power = config.power;
+        if let Fractal::Mandelbrot = self {
+            if config.cardioid_applies() && in_main_cardioid_or_bulb(point) {
+                return None;
+            }
+        }
+        if let Fractal::Newton = self {
+            return newton_root(*point, config.max_iter).map(|(n, k)| (n, NEWTON_ROOTS[k]));
+        }
+        let (z0, c) = self.start(point, config);
+        iterate(z0, config.max_iter, config.bailout_sqr, |z| self.step(z, c, power))
+    }
+
+    // Whether the escape of `conj(point)` always matches that of `point`.
+    // For `Newton` it converges to the conjugate root, which has another
+    // color.
+    fn conjugate_symmetric(&self) -> bool {
+        match self {
+            Fractal::Mandelbrot | Fractal::Tricorn => true,
+            Fractal::Julia(c) => c.im == 0.0,
+            Fractal::BurningShip | Fractal::Newton => false,
+        }
+    }
+
+    // The starting `z` and the constant `c` for a sampled point. `config.z0`
+    // only seeds the formulas that iterate over `c`.
+    fn start(&self, point: &Complex<f64>, config: &EscapeConfig) -> (Complex<f64>, Complex<f64>) {
+        match self {
+            Fractal::Julia(c) => (*point, *c),
+            Fractal::Newton => (*point, Complex::new(0.0, 0.0)),
+            _ => (config.z0, *point),
+        }
+    }
+
+    fn step(&self, z: Complex<f64>, c: Complex<f64>, power: u32) -> Complex<f64> {
+        match self {
+            Fractal::BurningShip => {
+                let z = Complex::new(z.re.abs(), z.im.abs());
+                z * z + c
+            }
+            Fractal::Tricorn => {
+                let z = z.conj();
+                z * z + c
+            }
+            Fractal::Newton => newton_step(z),
+            _ => step(z, c, power),
+        }
+    }
+
+    // Same convention as the free `distance_estimate`; approximate for the
+    // Burning Ship and the Tricorn, whose folding and conjugation the
+    // derivative ignores, and always `None` for `Newton`, which has no
+    // escape radius to measure from.
+    pub fn distance_estimate(&self, point: &Complex<f64>, config: &EscapeConfig) -> Option<f64> {
+        let power = config.power;
+        if let Fractal::Mandelbrot = self {
+            if config.cardioid_applies() && in_main_cardioid_or_bulb(point) {
+                return None;
+            }
+        }
+        if let Fractal::Newton = self {
+            return None;
+        }
+        let degree = match self {
+            Fractal::BurningShip | Fractal::Tricorn => 2,
+            _ => power,
+        };
+        let (mut z, c) = self.start(point, config);
+        // d(z0)/dc is 0 when `z0` is fixed and 1 when `z0` is the sampled point.
+        let (mut dz, dc) = match self {
+            Fractal::Julia(_) => (Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)),
+            _ => (Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)),
+        };
+        for _ in 0..config.max_iter {
+            dz = z.powu(degree - 1) * dz * degree as f64 + dc;
+            z = self.step(z, c, power);
+            if escaped(z, DISTANCE_BAILOUT) {
+                let r = z.norm();
+                return Some(r * r.ln() / dz.norm());
+            }
+        }
+        None
+    }
+
+    // The closest the orbit of `point` comes to `trap`, found by rerunning the
+    // escape loop with the distance tracked alongside. The starting `z` is
+    // skipped, since for the Mandelbrot set it is 0 for every point.
+    fn trap_distance(&self, point: &Complex<f64>, config: &EscapeConfig, trap: Trap) -> f64 {
+        self.trap_escape(point, config, trap).1
+    }
+
+    // The escape of `point` together with its `trap_distance`, from a single
+    // run of the escape loop.
+    fn trap_escape(
+        &self,
+        point: &Complex<f64>,
+        config: &EscapeConfig,
+        trap: Trap,
+    ) -> (Option<(usize, Complex<f64>)>, f64) {
+        let (z0, c) = self.start(point, config);
+        let closest = Cell::new(f64::INFINITY);
+        let escape = iterate(z0, config.max_iter, config.bailout_sqr, |z| {
+            let z = self.step(z, c, config.power);
+            closest.set(closest.get().min(trap.distance(z)));
+            z
+        });
+        (escape, closest.get())
+    }
+
+    // `z` after `max_iter` steps, with no bailout or periodicity check.
+    fn final_z(&self, point: &Complex<f64>, config: &EscapeConfig) -> Complex<f64> {
+        let (mut z, c) = self.start(point, config);
+        for _ in 0..config.max_iter {
+            z = self.step(z, c, config.power);
+        }
+        z
+    }
+
+    // The cycle length `iterate`'s periodicity check sees for `point`, or
+    // `None` if the orbit escapes or no cycle shows up within `max_iter`.
+    fn period(&self, point: &Complex<f64>, config: &EscapeConfig) -> Option<usize> {
+        match self.fate(point, config) {
+            Classification::PeriodicStable(period) => Some(period),
+            _ => None,
+        }
+    }
+
+    // How `iterate` ends for `point`, step for step, without the cardioid
+    // shortcut.
+    fn fate(&self, point: &Complex<f64>, config: &EscapeConfig) -> Classification {
+        let (power, bailout) = (config.power, config.bailout_sqr);
+        let (mut z, c) = self.start(point, config);
+        let mut reference = z;
+        let mut since = 0;
+        for n in 0..config.max_iter {
+            z = self.step(z, c, power);
+            since += 1;
+            if escaped(z, bailout) {
+                return Classification::Escaped(n);
+            }
+            if (z - reference).norm_sqr() < PERIODICITY_EPSILON * PERIODICITY_EPSILON {
+                // `since` may be a multiple of the period when the orbit was
+                // still spiralling in at the reference, so look for the
+                // shortest cycle from the settled `z`.
+                let mut w = z;
+                let period = (1..since).find(|_| {
+                    w = self.step(w, c, power);
+                    (w - z).norm_sqr() < PERIOD_TOLERANCE * PERIOD_TOLERANCE
+                });
+                return Classification::PeriodicStable(period.unwrap_or(since));
+            }
+            if (n + 1) % PERIODICITY_CHECK_INTERVAL == 0 {
+                reference = z;
+                since = 0;
+            }
+        }
+        Classification::ReachedMaxIter
+    }
+
+    // `-log_d(ln|z| / d^n)` for an escape `(n, z)`, worked out in log space
+    // since `d^n` overflows long before `n` reaches realistic limits.
+    fn potential_level(&self, (n, z): (usize, Complex<f64>), power: u32) -> f64 {
+        let degree = self.degree(power);
+        let level = n as f64 - z.norm().ln().ln() / degree.ln();
+        if level.is_finite() {
+            level
+        } else {
+            n as f64
+        }
+    }
+
+    fn degree(&self, power: u32) -> f64 {
+        match self {
+            Fractal::BurningShip | Fractal::Tricorn => 2.0,
+            _ => power as f64,
+        }
+    }
+
+    fn smooth_value(
+        &self,
+        point: &Complex<f64>,
+        (n, z): (usize, Complex<f64>),
+        config: &EscapeConfig,
+    ) -> f64 {
+        let power = config.power;
+        if let Fractal::Newton = self {
+            return n as f64;
+        }
+        // Extra iterations shrink the error of the log-log approximation,
+        // which is only exact for very large |z|.
+        let (_, c) = self.start(point, config);
+        let (m, z) = smoothing_escape((n, z), config, |z| self.step(z, c, power));
+        let smooth = m as f64 + 1.0 - z.norm().ln().ln() / self.degree(power).ln();
+        // An orbit that overflowed, as `escaped` allows, has only its count.
+        if smooth.is_finite() {
+            smooth
+        } else {
+            n as f64
+        }
+    }
+}
+
+// How an escaped point's iteration count becomes a position on the palette.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorMode {
+    // The escape iteration divided by the iteration limit.
+    #[default]
+    Linear,
+    // The fractional `smooth_escape_time` divided by the iteration limit,
+    // which removes banding.
+    Smooth,
+    // The fraction of escaped pixels that escaped no later than this one, so
+    // every palette position is used by roughly the same number of pixels.
+    Histogram,
+    // Ignores the iteration count and colors by how close the orbit came to
+    // the trap: the palette end at distance 0, its start at `TRAP_RANGE` and
+    // beyond.
+    OrbitTrap(Trap),
+    // The estimated distance to the set measured in pixels, `d`, as
+    // `1 / (1 + d)`: thin filaments stay bright even where no sample lands
+    // inside them.
+    Distance,
+    // The potential `G = ln|z| / d^n` at escape, for escape iteration `n` and
+    // degree `d`, taken as `-log_d(G)` and wrapped around the palette every
+    // `POTENTIAL_BAND` units, so each band lies between two equipotential
+    // lines.
+    Potential,
+    // Both the `Smooth` position on the palette and the `OrbitTrap`
+    // closeness, tracked in one pass: the color at the smooth position is
+    // darkened by the trap distance, from full brightness at distance 0 to
+    // black at `TRAP_RANGE` and beyond.
+    Composite { trap: Trap },
+}
+
+// How much `-log_d(G)` one pass through the palette covers in
+// `ColorMode::Potential`; one unit is one iteration's worth.
+pub const POTENTIAL_BAND: f64 = 8.0;
+
+// How points in the set are colored. The orbit is followed again in `f64`
+// to find the value, so only non-`Solid` modes pay for it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InteriorMode {
+    // Every point gets `RenderParams::interior`.
+    #[default]
+    Solid,
+    // `|z|` after `max_iter` iterations over the escape radius, through
+    // `interior_palette`.
+    Magnitude,
+    // The cycle length found by the periodicity check, from 1 at the start of
+    // `interior_palette` to `PERIODICITY_CHECK_INTERVAL` at its end, which
+    // gives each hyperbolic component one color. Points whose cycle isn't
+    // found in time get `RenderParams::interior`.
+    Period,
+}
+
+// A transform of the palette position chosen by `ColorMode`, applied just
+// before lookup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorScale {
+    #[default]
+    Linear,
+    // `ln(1 + t * max_iter) / ln(1 + max_iter)`, which for `ColorMode::Linear`
+    // is the log of the escape count. Spreads the many low counts near the
+    // boundary over more of the palette.
+    Log,
+}
+
+impl ColorScale {
+    pub fn apply(&self, t: f64, max_iter: usize) -> f64 {
+        match self {
+            ColorScale::Linear => t,
+            ColorScale::Log => {
+                let max_iter = max_iter.max(1) as f64;
+                (1.0 + t * max_iter).ln() / (1.0 + max_iter).ln()
+            }
+        }
+    }
+}
+
+// Distances past this map to the start of the palette in `ColorMode::OrbitTrap`.
+pub const TRAP_RANGE: f64 = 2.0;
+
+// The shape an orbit trap measures distance to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trap {
+    Point(Complex<f64>),
+    // The line through `through` at `angle` radians from the real axis.
+    Line { through: Complex<f64>, angle: f64 },
+    // The horizontal and vertical lines crossing at the given point.
+    Cross(Complex<f64>),
+}
+
+impl Trap {
+    pub fn distance(&self, z: Complex<f64>) -> f64 {
+        match *self {
+            Trap::Point(point) => (z - point).norm(),
+            Trap::Line { through, angle } => {
+                let offset = z - through;
+                (offset * Complex::from_polar(1.0, -angle)).im.abs()
+            }
+            Trap::Cross(center) => (z.re - center.re).abs().min((z.im - center.im).abs()),
+        }
+    }
+}
+
+// What RGBA renders do with points outside the set. RGB renders color them
+// from the palette unless `PreviewBackground` says otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Exterior {
+    #[default]
+    Palette,
+    // Fully transparent, for compositing the set onto other images.
+    Transparent,
+}
+
+// What RGB renders, which have no alpha to keep, show behind an
+// `Exterior::Transparent` exterior. RGBA renders are unaffected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PreviewBackground {
+    // The palette colors, as for `Exterior::Palette`.
+    #[default]
+    Palette,
+    // Gray and white squares of `CHECKER_SIZE` pixels, as image editors show
+    // transparency, with partially covered edge pixels blended over them.
+    Checkerboard,
+}
+
+// Noise added to exterior palette colors just before they are rounded to
+// bytes, which breaks the visible steps of a gradient that changes by less
+// than one level per pixel into a fine grain. Each channel moves by less
+// than one level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dither {
+    // Plain rounding, so renders stay byte-for-byte reproducible.
+    #[default]
+    Off,
+    // A repeating 4x4 Bayer threshold pattern.
+    Ordered,
+    // An independent threshold per pixel from a `StdRng` seeded by
+    // `RenderParams::seed` and the pixel, like `jitter`.
+    Random,
+}
+
+// The 4x4 Bayer matrix, thresholds `0..16` spread so that neighbours differ
+// as much as possible.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+pub const CHECKER_SIZE: u32 = 8;
+const CHECKER_LIGHT: u8 = 255;
+const CHECKER_DARK: u8 = 204;
+
+// Where the samples along each axis of a render fall. `render_tile` ignores
+// this and always samples cell centres; `complex_matrix` is always inclusive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sampling {
+    // `n` samples from `min` to `max` with both ends included, like
+    // `ndarray::linspace`, so the image shows the exact bounds it was given.
+    #[default]
+    Inclusive,
+    // `n` samples `(max - min) / n` apart starting at `min`, covering
+    // `[min, max)`. Regions that share an edge then tile without sampling the
+    // edge twice.
+    HalfOpen,
+}
+
+impl Sampling {
+    // The `n` sample coordinates from `min` towards `max`.
+    pub fn axis(&self, min: f64, max: f64, n: usize) -> Vec<f64> {
+        match self {
+            Sampling::Inclusive => linspace(min, max, n).collect(),
+            Sampling::HalfOpen => {
+                let step = (max - min) / n as f64;
+                (0..n).map(|k| min + k as f64 * step).collect()
+            }
+        }
+    }
+
+    // The distance between neighbouring samples of `axis`, or 0 for a single
+    // inclusive sample.
+    pub fn spacing(&self, min: f64, max: f64, n: usize) -> f64 {
+        match self {
+            Sampling::Inclusive if n > 1 => (max - min) / (n - 1) as f64,
+            Sampling::Inclusive => 0.0,
+            Sampling::HalfOpen => (max - min) / n.max(1) as f64,
+        }
+    }
+}
+
+// How many iterations a render allows before counting a point as in the set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IterationPolicy {
+    Fixed(usize),
+    // `base` at zoom 1 (see `Viewport`), plus another `base` for every
+    // doubling of the zoom, so deep views still have time to escape.
+    AutoZoom { base: usize },
+    // `suggest_max_iter`: `AutoZoom` from `SUGGESTED_BASE_ITER`, capped at
+    // `MAX_SUGGESTED_ITER`.
+    Suggested,
+}
+
+// The `AutoZoom` base `IterationPolicy::Suggested` starts from, enough for
+// the boundary at the full view to show its bulbs and filaments.
+pub const SUGGESTED_BASE_ITER: usize = 100;
+// Where `IterationPolicy::Suggested` stops growing, around zoom `2^200`,
+// far deeper than plain `f64` resolves; deeper views rarely need more, and every
+// interior pixel costs this many iterations.
+pub const MAX_SUGGESTED_ITER: usize = 20_000;
+
+impl IterationPolicy {
+    pub fn max_iter(&self, zoom: f64) -> usize {
+        match *self {
+            IterationPolicy::Fixed(max_iter) => max_iter,
+            IterationPolicy::AutoZoom { base } => {
+                (base as f64 * (1.0 + zoom.max(1.0).log2())).round() as usize
+            }
+            IterationPolicy::Suggested => IterationPolicy::AutoZoom { base: SUGGESTED_BASE_ITER }
+                .max_iter(zoom)
+                .min(MAX_SUGGESTED_ITER),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RenderParams {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    // Samples per unit of the complex plane along each axis, which sets the
+    // spatial resolution only; it must be positive.
+    pub pixel_density: i128,
+    // Exact `(width, height)` in samples; when set it replaces the sizes
+    // derived from `pixel_density`. Neither side may be 0.
+    pub size: Option<(u32, u32)>,
+    // Renders with either side of the grid above `max_dimension` are refused
+    // with `MandelbrotError::DimensionsTooLarge`, before anything is
+    // allocated, unless `allow_huge` is set.
+    pub max_dimension: u32,
+    pub allow_huge: bool,
+    pub sampling: Sampling,
+    // How deep each orbit is followed before a point counts as inside the
+    // set, independent of the resolution; it must resolve to at least 1.
+    pub iterations: IterationPolicy,
+    // Compared against `norm_sqr()`; see `DEFAULT_BAILOUT`. Raising it helps
+    // `ColorMode::Smooth`. The arbitrary-precision path always uses 4.0.
+    pub bailout: f64,
+    // A second, larger bailout for `ColorMode::Smooth` and
+    // `ColorMode::Composite`: escaped orbits keep iterating until
+    // `norm_sqr()` passes it before the fractional count is taken, which
+    // removes the noise a small `bailout` leaves in it, without changing
+    // which points escape or their `Linear` and `Histogram` colors. Each
+    // escaped sample costs a few more iterations, uncapped by `max_iter`.
+    // `None` takes the count two iterations past the escape; values no
+    // larger than `bailout` take it at the escape itself.
+    pub smoothing_bailout: Option<f64>,
+    pub power: u32,
+    // The `z` every orbit starts from, in place of 0, for the Mandelbrot,
+    // Burning Ship and Tricorn formulas; `Julia` and `Newton` start from the
+    // sampled point and ignore it. A nonzero seed loses the cardioid
+    // shortcut and perturbation, and unless it is real, mirroring too.
+    pub z0: Complex<f64>,
+    pub fractal: Fractal,
+    // Exterior points are colored by `palette` at the position chosen by
+    // `color_mode` and transformed by `color_scale`; points in the set use
+    // `interior` or, depending on `interior_mode`, `interior_palette`.
+    pub color_mode: ColorMode,
+    pub color_scale: ColorScale,
+    // Added to the scaled position before the palette lookup, wrapping
+    // around the palette modulo 1, so stepping it from frame to frame cycles
+    // the colors without touching the escape times. 0 leaves positions alone.
+    pub palette_offset: f64,
+    pub palette: Palette,
+    #[cfg_attr(feature = "serde", serde(with = "json::rgb"))]
+    pub interior: Rgb<u8>,
+    pub interior_mode: InteriorMode,
+    pub interior_palette: Palette,
+    pub exterior: Exterior,
+    pub preview_background: PreviewBackground,
+    // Darkens each `Fractal::Newton` root color by the iterations the point
+    // took to converge, from full brightness at 0 to black at `max_iter`.
+    pub newton_shading: bool,
+    // Each color channel `c` of the finished pixel becomes
+    // `255 * (c / 255)^(1 / gamma)`, after palette lookup and supersampling;
+    // 1.0 leaves colors alone and 2.2 brightens midtones for sRGB displays.
+    // Alpha is never changed.
+    pub gamma: f64,
+    // See `Dither`; applied before `gamma`.
+    pub dither: Dither,
+    // For exterior-focused renders: samples still bounded after this many
+    // iterations, and not already caught by the cardioid test or the
+    // periodicity check, are counted as in the set and get `interior`
+    // instead of running on to `max_iter`. Colors still span `max_iter`, so
+    // the only points lost are those that would have escaped late.
+    pub interior_budget: Option<usize>,
+    // Radians the window is turned counterclockwise about its centre; every
+    // sample of the upright `x_min..x_max` x `y_min..y_max` grid is rotated
+    // before it is evaluated. Rotated windows always sample in `f64` and are
+    // never mirrored.
+    pub rotation: f64,
+    pub precision: Precision,
+    // Each pixel averages the colors of a `samples_per_pixel` x
+    // `samples_per_pixel` grid of points spread across its footprint; 1
+    // samples just the pixel's own grid point. Only the `f64` path
+    // supersamples.
+    pub samples_per_pixel: u32,
+    // Spends the `samples_per_pixel` grid only on pixels at the edge of the
+    // set: each pixel's own grid point is sampled first, and only those whose
+    // membership differs from one of their four neighbours' are supersampled.
+    // The rest keep the single sample.
+    pub adaptive_sampling: bool,
+    // Moves each point of the `samples_per_pixel` grid to a random spot in
+    // its own cell of the pixel, which trades the grid's aliasing for noise.
+    // The spots come from a `StdRng` seeded by `seed` and the pixel alone, so
+    // a fixed `seed` gives byte-identical images however rows are scheduled.
+    // Has no effect with one sample per pixel.
+    pub jitter: bool,
+    // Caps the workers a render uses; `None` shares rayon's global pool.
+    // Ignored without the `parallel` feature.
+    pub threads: Option<usize>,
+    // Hands the image to the workers as `tile_size` x `tile_size` squares
+    // instead of whole rows, so the costly tiles along the boundary of the
+    // set are spread over more workers; see `DEFAULT_TILE_SIZE`. Progress is
+    // then reported per tile. The pixels are the same either way.
+    pub tile_size: Option<u32>,
+    // Seeds the random sampling of `buddhabrot_histogram`, of `jitter` and of
+    // `Dither::Random`; `None` draws a fresh seed every run.
+    pub seed: Option<u64>,
+    // When set, `render` and `render_with_progress` also save the image here,
+    // in the format its extension names (see `save`) and with the parameters
+    // embedded as by `save_with_params`.
+    #[cfg(feature = "fs")]
+    pub output: Option<PathBuf>,
+}
+
+impl Default for RenderParams {
+    fn default() -> Self {
+        let (x_min, x_max, y_min, y_max) = MANDELBROT_BOUNDS;
+        RenderParams {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            pixel_density: 8000,
+            size: None,
+            max_dimension: MAX_DIMENSION,
+            allow_huge: false,
+            sampling: Sampling::Inclusive,
+            iterations: IterationPolicy::Fixed(20),
+            bailout: DEFAULT_BAILOUT,
+            smoothing_bailout: None,
+            power: 2,
+            z0: Complex::new(0.0, 0.0),
+            fractal: Fractal::Mandelbrot,
+            color_mode: ColorMode::Linear,
+            color_scale: ColorScale::Linear,
+            palette_offset: 0.0,
+            palette: Palette::default(),
+            interior: Rgb([0, 0, 0]),
+            interior_mode: InteriorMode::Solid,
+            interior_palette: Palette::grayscale(),
+            exterior: Exterior::Palette,
+            preview_background: PreviewBackground::Palette,
+            newton_shading: false,
+            gamma: 1.0,
+            dither: Dither::Off,
+            interior_budget: None,
+            rotation: 0.0,
+            precision: Precision::Auto,
+            samples_per_pixel: 1,
+            adaptive_sampling: false,
+            jitter: false,
+            threads: None,
+            tile_size: None,
+            seed: None,
+            #[cfg(feature = "fs")]
+            output: None,
+        }
+    }
+}
+
+impl RenderParams {
+    // `(width, height)` of the sample grid, which is also the image size.
+    pub fn grid_size(&self) -> (usize, usize) {
+        match self.size {
+            Some((width, height)) => (width as usize, height as usize),
+            None => (
+                axis_len(self.x_min, self.x_max, self.pixel_density),
+                axis_len(self.y_min, self.y_max, self.pixel_density),
+            ),
+        }
+    }
+
+    // How far in these bounds are, measured like `Viewport::zoom`.
+    pub fn zoom(&self) -> f64 {
+        ZOOM_1_HEIGHT / (self.y_max - self.y_min)
+    }
+
+    // The iteration limit `iterations` works out to for these bounds.
+    pub fn max_iter(&self) -> usize {
+        self.iterations.max_iter(self.zoom())
+    }
+
+    // Where on `palette` the normalized value `t` lands: through
+    // `color_scale`, then shifted by `palette_offset`.
+    pub(crate) fn palette_position(&self, t: f64) -> f64 {
+        let t = self.color_scale.apply(t, self.max_iter());
+        if self.palette_offset == 0.0 {
+            return t;
+        }
+        (t + self.palette_offset).rem_euclid(1.0)
+    }
+
+    // The iterations a sample runs for: `max_iter`, cut to
+    // `interior_budget` if that is smaller.
+    fn iteration_budget(&self) -> usize {
+        self.max_iter().min(self.interior_budget.unwrap_or(usize::MAX))
+    }
+
+    // The escape settings every `f64` sample is iterated with.
+    pub fn escape_config(&self) -> EscapeConfig {
+        let config = EscapeConfig::new(self.iteration_budget())
+            .with_bailout_sqr(self.bailout)
+            .with_power(self.power)
+            .with_z0(self.z0);
+        match self.smoothing_bailout {
+            Some(bailout) => config.with_smoothing_bailout_sqr(bailout),
+            None => config,
+        }
+    }
+
+    // Whether RGB renders show the exterior as a checkerboard; see
+    // `PreviewBackground`.
+    fn checkerboard(&self) -> bool {
+        self.exterior == Exterior::Transparent
+            && self.preview_background == PreviewBackground::Checkerboard
+    }
+
+    // A quick draft of the same viewport: `PREVIEW_SCALE` times fewer samples
+    // along each axis, one sample per pixel and at most `PREVIEW_MAX_ITER`
+    // iterations. Nothing is saved to `output`.
+    pub fn preview(&self) -> RenderParams {
+        let shrink = |n: u32| (n / PREVIEW_SCALE).max(1);
+        RenderParams {
+            pixel_density: (self.pixel_density / PREVIEW_SCALE as i128).max(1),
+            size: self.size.map(|(width, height)| (shrink(width), shrink(height))),
+            iterations: IterationPolicy::Fixed(self.max_iter().min(PREVIEW_MAX_ITER)),
+            samples_per_pixel: 1,
+            #[cfg(feature = "fs")]
+            output: None,
+            ..self.clone()
+        }
+    }
+}
+
+// The default `RenderParams::max_dimension`: an 8192 x 8192 RGB image is
+// already close to 200 MB.
+pub const MAX_DIMENSION: u32 = 8192;
+
+// A `RenderParams::tile_size` small enough that a typical image splits into
+// many more tiles than there are cores.
+pub const DEFAULT_TILE_SIZE: u32 = 64;
+
+// How much `RenderParams::preview` shrinks each side of the image.
+pub const PREVIEW_SCALE: u32 = 10;
+pub const PREVIEW_MAX_ITER: usize = 100;
+
+pub fn render(params: &RenderParams) -> Result<RgbImage, MandelbrotError> {
+    render_with_progress(params, |_, _| {})
+}
+
+// `progress` receives `(completed_rows, total_rows)` after every finished
+// row. Calls are serialized, so the count only ever goes up even when rows
+// are rendered in parallel.
+pub fn render_with_progress<P>(
+    params: &RenderParams,
+    mut progress: P,
+) -> Result<RgbImage, MandelbrotError>
+where
+    P: FnMut(usize, usize) + Send,
+{
+    let image = render_fractal(params, params.fractal, &mut progress)?;
+    save_output(params, &image)?;
+    Ok(image)
+}
+
+#[cfg(feature = "fs")]
+fn save_output(params: &RenderParams, image: &RgbImage) -> Result<(), MandelbrotError> {
+    if let Some(path) = &params.output {
+        save_with_params(image, params, path, OutputFormat::for_path(path)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "fs"))]
+fn save_output(_params: &RenderParams, _image: &RgbImage) -> Result<(), MandelbrotError> {
+    Ok(())
+}
+
+pub fn render_mandelbrot(params: &RenderParams) -> Result<RgbImage, MandelbrotError> {
+    render_fractal(params, Fractal::Mandelbrot, &mut |_, _| {})
+}
+
+// Each pixel is the starting `z`; `c` stays fixed for the whole image.
+pub fn render_julia(c: Complex<f64>, params: &RenderParams) -> Result<RgbImage, MandelbrotError> {
+    render_fractal(params, Fractal::Julia(c), &mut |_, _| {})
+}
+
+type Progress<'a> = &'a mut (dyn FnMut(usize, usize) + Send);
+
+// One pixel per grid sample; the grid's first row is `y_min`, which ends up
+// at the bottom of the image.
+// Renders into RGBA with `params.exterior` applied.
+pub fn render_rgba_image(params: &RenderParams) -> Result<RgbaImage, MandelbrotError> {
+    render_fractal(params, params.fractal, &mut |_, _| {})
+}
+
+// `render_rgba_image` as raw RGBA bytes, row-major from the top, ready to
+// hand to a canvas `ImageData`. Touches no files, and with the `parallel`
+// feature off uses no threads, so it works on `wasm32-unknown-unknown`.
+pub fn render_rgba(params: &RenderParams) -> Result<Vec<u8>, MandelbrotError> {
+    render_rgba_image(params).map(RgbaImage::into_raw)
+}
+
+// Like `render`, but writes into `target` instead of allocating a new image,
+// so a redraw loop can reuse one buffer. `target` must already have the size
+// of `params.grid_size()`. `params.output` is not saved.
+pub fn render_into(params: &RenderParams, target: &mut RgbImage) -> Result<(), MandelbrotError> {
+    render_fractal_into(params, params.fractal, target, &mut |_, _| {})
+}
+
+fn render_fractal<P: Pixel<Subpixel = u8> + Send>(
+    params: &RenderParams,
+    fractal: Fractal,
+    progress: Progress,
+) -> Result<ImageBuffer<P, Vec<u8>>, MandelbrotError> {
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    let mut image = ImageBuffer::new(width as u32, height as u32);
+    render_fractal_into(params, fractal, &mut image, progress)?;
+    Ok(image)
+}
+
+fn render_fractal_into<P: Pixel<Subpixel = u8> + Send>(
+    params: &RenderParams,
+    fractal: Fractal,
+    target: &mut ImageBuffer<P, Vec<u8>>,
+    progress: Progress,
+) -> Result<(), MandelbrotError> {
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    if target.dimensions() != (width as u32, height as u32) {
+        return Err(MandelbrotError::SizeMismatch {
+            expected: (width as u32, height as u32),
+            found: target.dimensions(),
+        });
+    }
+    with_threads(params.threads, || {
+        with_fractal_shader(params, fractal, |shader| {
+            shader.rows_into(0..shader.height, target, progress);
+        })
+    })
+}
+
+// What a render did, for tuning. Counts are per sample, so with
+// `samples_per_pixel` above 1 each pixel contributes that many squared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub total_pixels: u64,
+    // Samples that never escaped, however that was decided: the cardioid
+    // test, periodicity detection or reaching the iteration limit.
+    pub interior_pixels: u64,
+    pub escaped_pixels: u64,
+    // The most iterations any escaped sample took, counting the one that
+    // crossed the bailout.
+    pub max_orbit_length: usize,
+    pub elapsed: Duration,
+    pub path: RenderPath,
+}
+
+impl RenderStats {
+    fn count(&mut self, (_, escape): &Sample) {
+        self.total_pixels += 1;
+        match escape {
+            Some((n, _)) => {
+                self.escaped_pixels += 1;
+                self.max_orbit_length = self.max_orbit_length.max(n + 1);
+            }
+            None => self.interior_pixels += 1,
+        }
+    }
+
+    fn merge(self, other: RenderStats) -> RenderStats {
+        RenderStats {
+            total_pixels: self.total_pixels + other.total_pixels,
+            interior_pixels: self.interior_pixels + other.interior_pixels,
+            escaped_pixels: self.escaped_pixels + other.escaped_pixels,
+            max_orbit_length: self.max_orbit_length.max(other.max_orbit_length),
+            elapsed: self.elapsed + other.elapsed,
+            path: self.path,
+        }
+    }
+}
+
+// Like `render`, also reporting how many samples escaped and how long the
+// whole render took, including saving to `params.output`.
+pub fn render_with_stats(
+    params: &RenderParams,
+) -> Result<(RgbImage, RenderStats), MandelbrotError> {
+    let start = Instant::now();
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    let mut image = ImageBuffer::new(width as u32, height as u32);
+    let mut stats = with_threads(params.threads, || {
+        with_fractal_shader(params, params.fractal, |shader| {
+            shader.rows_into(0..shader.height, &mut image, &mut |_, _| {})
+        })
+    })?;
+    save_output(params, &image)?;
+    stats.elapsed = start.elapsed();
+    stats.path = sampler_path(params, params.fractal);
+    Ok((image, stats))
+}
+
+// The sampler a render iterates its points with. `render` picks it by
+// itself: fixed point when the zoom or `precision` calls for it, otherwise
+// the vector loop of `escape_times` when the `simd` feature is compiled in,
+// the CPU has vector instructions and the formula is the quadratic
+// Mandelbrot from 0 with one sample per pixel, and the scalar loop for
+// everything else. `Scalar` and `Simd` give identical images.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderPath {
+    #[default]
+    Scalar,
+    Simd,
+    // `Precision::Perturbation`, with a reference orbit of this many
+    // fractional bits.
+    Perturbation { bits: u32 },
+    // Every point in `BigInt` fixed point with this many fractional bits.
+    Arbitrary { bits: u32 },
+}
+
+// The `RenderPath` `render` takes for `params`.
+pub fn render_path(params: &RenderParams) -> RenderPath {
+    sampler_path(params, params.fractal)
+}
+
+fn sampler_path(params: &RenderParams, fractal: Fractal) -> RenderPath {
+    let (width, height) = params.grid_size();
+    let perturbs = params.precision == Precision::Perturbation
+        && params.rotation == 0.0
+        && params.power == 2
+        && params.z0 == Complex::new(0.0, 0.0)
+        && matches!(fractal, Fractal::Mandelbrot | Fractal::Julia(_));
+    if perturbs && width >= 2 && height >= 2 {
+        let (spacing, magnitude) = window_scale(params, fractal, width, height);
+        let bits = precise::reference_bits(spacing, magnitude);
+        return RenderPath::Perturbation { bits };
+    }
+    if let Some(bits) = precise_bits(params, fractal, width, height) {
+        return RenderPath::Arbitrary { bits };
+    }
+    let vectorizes = fractal == Fractal::Mandelbrot
+        && params.escape_config().cardioid_applies()
+        && params.samples_per_pixel == 1;
+    if cfg!(feature = "simd") && vectorizes && simd_available() {
+        return RenderPath::Simd;
+    }
+    RenderPath::Scalar
+}
+
+// Whether the CPU runs `wide`'s vectors as vector instructions rather than
+// emulating them one lane at a time.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn simd_available() -> bool {
+    std::arch::is_x86_feature_detected!("sse2")
+}
+
+#[cfg(target_arch = "aarch64")]
+fn simd_available() -> bool {
+    std::arch::is_aarch64_feature_detected!("neon")
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+fn simd_available() -> bool {
+    cfg!(target_feature = "simd128")
+}
+
+// How many grid points along a row `RenderPath::Simd` iterates at once, ahead
+// of the shader asking for them.
+#[cfg(feature = "simd")]
+const SIMD_RUN: usize = 64;
+
+#[cfg(feature = "simd")]
+type Escape = Option<(usize, Complex<f64>)>;
+
+#[cfg(feature = "simd")]
+thread_local! {
+    // The escapes of the last run `RenderPath::Simd` iterated on this thread,
+    // by render, row and first column.
+    static SIMD_RUNS: std::cell::RefCell<(u64, usize, usize, Vec<Escape>)> =
+        const { std::cell::RefCell::new((0, 0, 0, Vec::new())) };
+}
+
+// Tells apart the runs of different renders in `SIMD_RUNS`.
+#[cfg(feature = "simd")]
+static SIMD_RENDERS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+// The escape of grid point `(i, j)` of render `render`, taken from the
+// thread's current run if it covers the point and otherwise from the new run
+// `iterate(j)` starting there.
+#[cfg(feature = "simd")]
+fn simd_escape(
+    render: u64,
+    (i, j): (usize, usize),
+    iterate: impl FnOnce(usize) -> Vec<Escape>,
+) -> Escape {
+    SIMD_RUNS.with(|runs| {
+        let (id, row, start, escapes) = &mut *runs.borrow_mut();
+        if (*id, *row) != (render, i) || !(*start..*start + escapes.len()).contains(&j) {
+            (*id, *row, *start) = (render, i, j);
+            *escapes = iterate(j);
+        }
+        escapes[j - *start]
+    })
+}
+
+// The point sampler behind every full-window render.
+type SampleFn<'a> = dyn Fn(usize, usize, (f64, f64)) -> Sample + Sync + 'a;
+
+// Builds the shader that `render` uses for `fractal` over the window in
+// `params`, picking the `f64` or fixed-point sampler, and hands it to `f`.
+fn with_fractal_shader<R>(
+    params: &RenderParams,
+    fractal: Fractal,
+    f: impl FnOnce(&Shader<&SampleFn>) -> R,
+) -> R {
+    with_mirrored_shader(params, fractal, true, f)
+}
+
+// `with_fractal_shader`, but with `mirror` false every row is shaded, for
+// checking the mirrored rows against.
+fn with_mirrored_shader<R>(
+    params: &RenderParams,
+    fractal: Fractal,
+    mirror: bool,
+    f: impl FnOnce(&Shader<&SampleFn>) -> R,
+) -> R {
+    let (width, height) = params.grid_size();
+    let path = sampler_path(params, fractal);
+    match path {
+        RenderPath::Perturbation { bits } => {
+            let size = (width, height);
+            return perturbation::with_perturbation_shader(params, fractal, size, bits, f);
+        }
+        RenderPath::Arbitrary { bits } => {
+            return with_precise_shader(params, fractal, (width, height), bits, f);
+        }
+        RenderPath::Scalar | RenderPath::Simd => {}
+    }
+
+    // Only the two axes are kept; each row builds its points as it is shaded,
+    // so memory beyond the image itself stays proportional to one row rather
+    // than the `complex_matrix` of the whole window.
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    // Conjugate points have exactly conjugate orbits, as long as the seed
+    // `z0` is real, so a window centred on the real axis only needs its lower
+    // half shaded. Rounding can leave a sample of the upper half a bit off
+    // the negated lower one, so only the rows that match bit for bit are
+    // copied.
+    let symmetric = mirror
+        && params.y_min == -params.y_max
+        && params.sampling == Sampling::Inclusive
+        && fractal.conjugate_symmetric()
+        && (params.z0.im == 0.0 || matches!(fractal, Fractal::Julia(_)))
+        && !matches!(params.color_mode, ColorMode::OrbitTrap(_) | ColorMode::Composite { .. })
+        && !params.checkerboard()
+        && !params.jitter
+        && params.dither == Dither::Off
+        && params.rotation == 0.0
+        && antisymmetric(&sample_offsets(params.samples_per_pixel));
+    let mirror = if symmetric {
+        (0..height).map(|y| im[height - 1 - y] == -im[y]).collect()
+    } else {
+        Vec::new()
+    };
+    let dx = params.sampling.spacing(params.x_min, params.x_max, width);
+    let dy = params.sampling.spacing(params.y_min, params.y_max, height);
+    let config = params.escape_config();
+    #[cfg(feature = "simd")]
+    let render = SIMD_RENDERS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let sample = |i: usize, j: usize, (ox, oy): (f64, f64)| {
+        let point = params.rotated(Complex::new(re[j] + ox * dx, im[i] + oy * dy));
+        #[cfg(feature = "simd")]
+        if path == RenderPath::Simd && (ox, oy) == (0.0, 0.0) {
+            let escape = simd_escape(render, (i, j), |start| {
+                let points = (start..(start + SIMD_RUN).min(width))
+                    .map(|j| params.rotated(Complex::new(re[j], im[i])))
+                    .collect::<Vec<_>>();
+                batch::simd_escapes(&points, &config, Lanes::default())
+            });
+            return (point, escape);
+        }
+        (point, fractal.escape(&point, &config))
+    };
+
+    let mut shader = Shader::new(
+        params,
+        fractal,
+        (width, height),
+        dx.max(dy),
+        params.samples_per_pixel,
+        &sample as &SampleFn,
+    );
+    shader.mirror = mirror;
+    f(&shader)
+}
+
+fn antisymmetric(offsets: &[f64]) -> bool {
+    offsets.iter().zip(offsets.iter().rev()).all(|(a, b)| *a == -*b)
+}
+
+// Runs `f` on a pool of `threads` workers, or on rayon's global pool when
+// `threads` is `None`. Without the `parallel` feature `f` just runs here.
+pub(crate) fn with_threads<T, F>(threads: Option<usize>, f: F) -> Result<T, MandelbrotError>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|err| MandelbrotError::ThreadPool(err.to_string()))?;
+        return Ok(pool.install(f));
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = threads;
+    Ok(f())
+}
+
+// A sampled point together with its escape iteration and the first `z`
+// outside the bailout radius, if it escaped.
+type Sample = (Complex<f64>, Option<(usize, Complex<f64>)>);
+
+// Colors a `width` x `height` image from `sample(row, column, offset)`, where
+// `row` counts up from `y_min` and `offset` is the sub-pixel position in units
+// of the grid spacing. With `samples_per_pixel` above 1 each pixel averages
+// the colors of an evenly spaced grid of offsets. `spacing` is the distance
+// between neighbouring pixels in the complex plane.
+fn shade_samples<P, S>(
+    params: &RenderParams,
+    fractal: Fractal,
+    (width, height): (usize, usize),
+    spacing: f64,
+    samples_per_pixel: u32,
+    sample: S,
+    progress: Progress,
+) -> ImageBuffer<P, Vec<u8>>
+where
+    P: Pixel<Subpixel = u8>,
+    S: Fn(usize, usize, (f64, f64)) -> Sample + Sync,
+{
+    Shader::new(params, fractal, (width, height), spacing, samples_per_pixel, sample)
+        .rows(0..height, progress)
+}
+
+// The state behind `shade_samples`, kept so that any subset of the image's
+// rows can be colored on its own and still match a full render, including
+// the histogram, which is always built over the whole image.
+struct Shader<'a, S> {
+    params: &'a RenderParams,
+    fractal: Fractal,
+    config: EscapeConfig,
+    width: usize,
+    height: usize,
+    spacing: f64,
+    offsets: Vec<f64>,
+    sample: S,
+    cdf: Option<Vec<f64>>,
+    // For `RenderParams::adaptive_sampling`, whether each grid point, row by
+    // row from `y_min`, is one to supersample.
+    edges: Option<Vec<bool>>,
+    // The seed for `RenderParams::jitter`, when it applies.
+    jitter: Option<u64>,
+    // The seed for `Dither::Random`, when it applies.
+    dither: Option<u64>,
+    gamma: Option<[u8; 256]>,
+    // Whether image row `y` is known to repeat row `height - 1 - y`, so only
+    // one of each pair is shaded. Empty when no rows mirror.
+    mirror: Vec<bool>,
+}
+
+impl<'a, S> Shader<'a, S>
+where
+    S: Fn(usize, usize, (f64, f64)) -> Sample + Sync,
+{
+    fn new(
+        params: &'a RenderParams,
+        fractal: Fractal,
+        (width, height): (usize, usize),
+        spacing: f64,
+        samples_per_pixel: u32,
+        sample: S,
+    ) -> Self {
+        let cdf = match params.color_mode {
+            ColorMode::Histogram => Some(escape_cdf(params.max_iter(), (width, height), &sample)),
+            _ => None,
+        };
+        let offsets = sample_offsets(samples_per_pixel);
+        let edges = (params.adaptive_sampling && offsets.len() > 1)
+            .then(|| membership_edges((width, height), &sample));
+        let jitter = (params.jitter && offsets.len() > 1)
+            .then(|| params.seed.unwrap_or_else(buddhabrot::fresh_seed));
+        let dither = (params.dither == Dither::Random)
+            .then(|| params.seed.unwrap_or_else(buddhabrot::fresh_seed));
+        Shader {
+            params,
+            fractal,
+            config: params.escape_config(),
+            width,
+            height,
+            spacing,
+            offsets,
+            sample,
+            cdf,
+            edges,
+            jitter,
+            dither,
+            gamma: gamma_table(params.gamma),
+            mirror: Vec::new(),
+        }
+    }
+
+    // Colors are RGBA until they are written, so that transparent exterior
+    // samples average into partial coverage at the edge of the set. `dither`
+    // is the pixel's `Dither` offset, in `-0.5..0.5` levels.
+    fn shade(&self, (point, escape): Sample, transparent: bool, dither: f64) -> [u8; 4] {
+        let params = self.params;
+        let [r, g, b] = params.interior.0;
+        match escape {
+            None => {
+                let [r, g, b] = self.interior_color(&point).0;
+                [r, g, b, u8::MAX]
+            }
+            Some((n, root)) if self.fractal == Fractal::Newton => {
+                let k = NEWTON_ROOTS.iter().position(|r| *r == root).unwrap_or(0);
+                let shade = if params.newton_shading {
+                    1.0 - n as f64 / params.max_iter() as f64
+                } else {
+                    1.0
+                };
+                let [r, g, b] = NEWTON_COLORS[k].0.map(|c| (c as f64 * shade).round() as u8);
+                [r, g, b, u8::MAX]
+            }
+            Some(_) if transparent && params.exterior == Exterior::Transparent => [r, g, b, 0],
+            Some((n, z)) => {
+                let mut brightness = 1.0;
+                let t = match (&self.cdf, params.color_mode) {
+                    (Some(cdf), _) => cdf[n],
+                    (None, ColorMode::OrbitTrap(trap)) => {
+                        let distance = self.fractal.trap_distance(&point, &self.config, trap);
+                        1.0 - (distance / TRAP_RANGE).min(1.0)
+                    }
+                    (None, ColorMode::Distance) => {
+                        let distance = self
+                            .fractal
+                            .distance_estimate(&point, &self.config)
+                            .unwrap_or(0.0);
+                        1.0 / (1.0 + distance / self.spacing)
+                    }
+                    (None, ColorMode::Potential) => {
+                        let level = self.fractal.potential_level((n, z), params.power);
+                        (level / POTENTIAL_BAND).rem_euclid(1.0)
+                    }
+                    (None, ColorMode::Smooth) => {
+                        let smooth = self.fractal.smooth_value(&point, (n, z), &self.config);
+                        smooth / params.max_iter() as f64
+                    }
+                    (None, ColorMode::Composite { trap }) => {
+                        let (escape, distance) =
+                            self.fractal.trap_escape(&point, &self.config, trap);
+                        brightness = 1.0 - (distance / TRAP_RANGE).min(1.0);
+                        // The fixed-point samplers may see an escape `f64` does not.
+                        let escape = escape.unwrap_or((n, z));
+                        self.fractal.smooth_value(&point, escape, &self.config)
+                            / params.max_iter() as f64
+                    }
+                    (None, _) => n as f64 / params.max_iter() as f64,
+                };
+                let t = params.palette_position(t);
+                let color = match params.dither {
+                    Dither::Off => params.palette.color(t).0,
+                    _ => params
+                        .palette
+                        .exact_color(t)
+                        .map(|c| (c + dither).round().clamp(0.0, 255.0) as u8),
+                };
+                let [r, g, b] = color.map(|c| (c as f64 * brightness).round() as u8);
+                [r, g, b, u8::MAX]
+            }
+        }
+    }
+
+    fn interior_color(&self, point: &Complex<f64>) -> Rgb<u8> {
+        let params = self.params;
+        let t = match params.interior_mode {
+            InteriorMode::Solid => None,
+            InteriorMode::Magnitude => {
+                let z = self.fractal.final_z(point, &self.config);
+                Some(z.norm() / params.bailout.sqrt())
+            }
+            InteriorMode::Period => self
+                .fractal
+                .period(point, &self.config)
+                .map(|period| (period - 1) as f64 / (PERIODICITY_CHECK_INTERVAL - 1) as f64),
+        };
+        match t {
+            Some(t) => params.interior_palette.color(t),
+            None => params.interior,
+        }
+    }
+
+    // The color of column `x` in image row `y`, counted from the top. Every
+    // sample taken is added to `stats`.
+    fn pixel(&self, x: usize, y: usize, transparent: bool, stats: &mut RenderStats) -> [u8; 4] {
+        let checkered = !transparent && self.params.checkerboard();
+        let [r, g, b, a] = self.average(x, y, transparent || checkered, stats);
+        let color = match &self.gamma {
+            Some(table) => [table[r as usize], table[g as usize], table[b as usize], a],
+            None => [r, g, b, a],
+        };
+        if !checkered {
+            return color;
+        }
+        let square = (x / CHECKER_SIZE as usize + y / CHECKER_SIZE as usize) % 2;
+        let background = if square == 0 { CHECKER_LIGHT } else { CHECKER_DARK } as u32;
+        let alpha = a as u32;
+        let [r, g, b] = [color[0], color[1], color[2]]
+            .map(|c| ((c as u32 * alpha + background * (255 - alpha) + 127) / 255) as u8);
+        [r, g, b, u8::MAX]
+    }
+
+    fn average(&self, x: usize, y: usize, transparent: bool, stats: &mut RenderStats) -> [u8; 4] {
+        let i = self.height - 1 - y;
+        let n = self.offsets.len() as u32;
+        let smooth = self.edges.as_ref().is_some_and(|edges| !edges[i * self.width + x]);
+        let dither = self.dither_offset(x, y);
+        if n == 1 || smooth {
+            let sample = (self.sample)(i, x, (0.0, 0.0));
+            stats.count(&sample);
+            return self.shade(sample, transparent, dither);
+        }
+        let mut sum = [0u32; 4];
+        let mut jitter = self
+            .jitter
+            .map(|seed| StdRng::seed_from_u64(seed.wrapping_add((i * self.width + x) as u64)));
+        let cell = 1.0 / n as f64;
+        for oy in &self.offsets {
+            for ox in &self.offsets {
+                let offset = match &mut jitter {
+                    Some(rng) => (
+                        ox + rng.gen_range(-0.5..0.5) * cell,
+                        oy + rng.gen_range(-0.5..0.5) * cell,
+                    ),
+                    None => (*ox, *oy),
+                };
+                let sample = (self.sample)(i, x, offset);
+                stats.count(&sample);
+                let color = self.shade(sample, transparent, dither);
+                for (total, channel) in sum.iter_mut().zip(color) {
+                    *total += channel as u32;
+                }
+            }
+        }
+        let count = n * n;
+        sum.map(|total| ((total + count / 2) / count) as u8)
+    }
+
+    // The `RenderParams::dither` offset of column `x` in image row `y`.
+    fn dither_offset(&self, x: usize, y: usize) -> f64 {
+        match self.params.dither {
+            Dither::Off => 0.0,
+            Dither::Ordered => (BAYER[y % 4][x % 4] as f64 + 0.5) / 16.0 - 0.5,
+            Dither::Random => {
+                let seed = self.dither.unwrap_or(0).wrapping_add((y * self.width + x) as u64);
+                StdRng::seed_from_u64(seed).gen_range(-0.5..0.5)
+            }
+        }
+    }
+
+    // An image of just the image rows in `rows`, counted from the top.
+    fn rows<P>(&self, rows: Range<usize>, progress: Progress) -> ImageBuffer<P, Vec<u8>>
+    where
+        P: Pixel<Subpixel = u8>,
+    {
+        let mut image = ImageBuffer::new(self.width as u32, rows.len() as u32);
+        self.rows_into(rows, &mut image, progress);
+        image
+    }
+
+    // Fills `image`, which is `rows.len()` rows tall, with the image rows in
+    // `rows`, and returns the sample counts for them (`elapsed` is left at
+    // zero). `progress` counts only the rows actually shaded.
+    fn rows_into<P>(
+        &self,
+        rows: Range<usize>,
+        image: &mut ImageBuffer<P, Vec<u8>>,
+        progress: Progress,
+    ) -> RenderStats
+    where
+        P: Pixel<Subpixel = u8>,
+    {
+        let channels = P::CHANNEL_COUNT as usize;
+        let transparent = channels == 4;
+        let copied = |y: usize| self.mirror_of(rows.start + y).filter(|m| rows.contains(m));
+        let mut row_stats = match self.params.tile_size {
+            Some(tile) => self.tiles_into(&rows, image, progress, tile as usize, &copied),
+            None => {
+                let row_stats = Mutex::new(vec![RenderStats::default(); rows.len()]);
+                for_each_row(image, progress, |y| copied(y).is_some(), |y, row| {
+                    let mut stats = RenderStats::default();
+                    for (x, pixel) in row.chunks_exact_mut(channels).enumerate() {
+                        let color = self.pixel(x, rows.start + y, transparent, &mut stats);
+                        pixel.copy_from_slice(&color[..channels]);
+                    }
+                    row_stats.lock().unwrap()[y] = stats;
+                });
+                row_stats.into_inner().unwrap()
+            }
+        };
+
+        let stride = self.width * channels;
+        for y in 0..rows.len() {
+            if let Some(m) = copied(y) {
+                let m = m - rows.start;
+                image.copy_within(m * stride..(m + 1) * stride, y * stride);
+                row_stats[y] = row_stats[m];
+            }
+        }
+        row_stats.into_iter().fold(RenderStats::default(), RenderStats::merge)
+    }
+
+    // `rows_into` for `RenderParams::tile_size`: the rows are cut into
+    // `tile` x `tile` squares, shaded independently, spread across threads
+    // when the `parallel` feature is on, and then copied into place. Rows for
+    // which `copied` is set are left alone. Returns the stats of each row.
+    fn tiles_into<P>(
+        &self,
+        rows: &Range<usize>,
+        image: &mut ImageBuffer<P, Vec<u8>>,
+        progress: Progress,
+        tile: usize,
+        copied: &(dyn Fn(usize) -> Option<usize> + Sync),
+    ) -> Vec<RenderStats>
+    where
+        P: Pixel<Subpixel = u8>,
+    {
+        let channels = P::CHANNEL_COUNT as usize;
+        let transparent = channels == 4;
+        let (width, height) = (self.width, rows.len());
+        let tile = tile.max(1);
+        let tiles = (0..height)
+            .step_by(tile)
+            .flat_map(|y| {
+                (0..width)
+                    .step_by(tile)
+                    .map(move |x| (y..(y + tile).min(height), x..(x + tile).min(width)))
+            })
+            .collect::<Vec<_>>();
+        let total = tiles.len();
+        let completed = Mutex::new((0, progress));
+        let shade = |(ys, xs): &(Range<usize>, Range<usize>)| {
+            let mut pixels = Vec::with_capacity(ys.len() * xs.len() * channels);
+            let mut stats = vec![RenderStats::default(); ys.len()];
+            for (y, stats) in ys.clone().zip(&mut stats) {
+                if copied(y).is_some() {
+                    pixels.resize(pixels.len() + xs.len() * channels, 0);
+                    continue;
+                }
+                for x in xs.clone() {
+                    let color = self.pixel(x, rows.start + y, transparent, stats);
+                    pixels.extend_from_slice(&color[..channels]);
+                }
+            }
+            let mut completed = completed.lock().unwrap();
+            completed.0 += 1;
+            let done = completed.0;
+            (completed.1)(done, total);
+            (pixels, stats)
+        };
+
+        #[cfg(feature = "parallel")]
+        let shaded = tiles.par_iter().map(shade).collect::<Vec<_>>();
+        #[cfg(not(feature = "parallel"))]
+        let shaded = tiles.iter().map(shade).collect::<Vec<_>>();
+
+        let mut row_stats = vec![RenderStats::default(); height];
+        let stride = width * channels;
+        let buffer: &mut [u8] = image;
+        for ((ys, xs), (pixels, stats)) in tiles.iter().zip(shaded) {
+            let span = xs.len() * channels;
+            for (k, y) in ys.clone().enumerate() {
+                let start = y * stride + xs.start * channels;
+                buffer[start..start + span].copy_from_slice(&pixels[k * span..(k + 1) * span]);
+                row_stats[y] = row_stats[y].merge(stats[k]);
+            }
+        }
+        row_stats
+    }
+
+    // The earlier image row that row `y` repeats, if any.
+    fn mirror_of(&self, y: usize) -> Option<usize> {
+        let m = self.height - 1 - y;
+        (m < y && self.mirror.get(y) == Some(&true)).then_some(m)
+    }
+}
+
+// The lookup table for `RenderParams::gamma`, or `None` when it is 1.
+pub(crate) fn gamma_table(gamma: f64) -> Option<[u8; 256]> {
+    (gamma != 1.0).then(|| {
+        std::array::from_fn(|c| (255.0 * (c as f64 / 255.0).powf(1.0 / gamma)).round() as u8)
+    })
+}
+
+// For each escape iteration, the fraction of escaped pixel centres that
+// escaped at or before it.
+fn escape_cdf<S>(max_iter: usize, (width, height): (usize, usize), sample: &S) -> Vec<f64>
+where
+    S: Fn(usize, usize, (f64, f64)) -> Sample + Sync,
+{
+    let row_counts = |i: usize| {
+        let mut counts = vec![0u64; max_iter];
+        for j in 0..width {
+            if let (_, Some((n, _))) = sample(i, j, (0.0, 0.0)) {
+                counts[n] += 1;
+            }
+        }
+        counts
+    };
+    let add = |mut a: Vec<u64>, b: Vec<u64>| {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+        a
+    };
+
+    #[cfg(feature = "parallel")]
+    let counts = (0..height)
+        .into_par_iter()
+        .map(row_counts)
+        .reduce(|| vec![0; max_iter], add);
+    #[cfg(not(feature = "parallel"))]
+    let counts = (0..height).map(row_counts).fold(vec![0; max_iter], add);
+
+    let total = counts.iter().sum::<u64>().max(1) as f64;
+    let mut running = 0;
+    counts
+        .iter()
+        .map(|count| {
+            running += count;
+            running as f64 / total
+        })
+        .collect()
+}
+
+// Which grid points are in or out of the set differently from at least one
+// of their four neighbours, judged by the single sample at each point.
+fn membership_edges<S>((width, height): (usize, usize), sample: &S) -> Vec<bool>
+where
+    S: Fn(usize, usize, (f64, f64)) -> Sample + Sync,
+{
+    let row_members = |i: usize| -> Vec<bool> {
+        (0..width).map(|j| sample(i, j, (0.0, 0.0)).1.is_none()).collect()
+    };
+    #[cfg(feature = "parallel")]
+    let members: Vec<bool> = (0..height).into_par_iter().flat_map(row_members).collect();
+    #[cfg(not(feature = "parallel"))]
+    let members: Vec<bool> = (0..height).flat_map(row_members).collect();
+
+    let member = |i: usize, j: usize| members[i * width + j];
+    (0..height)
+        .flat_map(|i| (0..width).map(move |j| (i, j)))
+        .map(|(i, j)| {
+            let here = member(i, j);
+            (i > 0 && member(i - 1, j) != here)
+                || (i + 1 < height && member(i + 1, j) != here)
+                || (j > 0 && member(i, j - 1) != here)
+                || (j + 1 < width && member(i, j + 1) != here)
+        })
+        .collect()
+}
+
+// Sub-pixel positions along each axis, in units of the grid spacing, for an
+// `n` x `n` supersampling grid.
+fn sample_offsets(n: u32) -> Vec<f64> {
+    let n = n.max(1);
+    (0..n).map(|k| (k as f64 + 0.5) / n as f64 - 0.5).collect()
+}
+
+fn validate(params: &RenderParams) -> Result<(), MandelbrotError> {
+    validate_bounds(
+        params.x_min,
+        params.x_max,
+        params.y_min,
+        params.y_max,
+        params.pixel_density,
+    )?;
+    match params.size {
+        Some((width, height)) if width == 0 || height == 0 => {
+            return Err(MandelbrotError::InvalidSize { width, height });
+        }
+        Some(_) => {}
+        None => validate_samples(
+            params.x_min,
+            params.x_max,
+            params.y_min,
+            params.y_max,
+            params.pixel_density,
+        )?,
+    }
+    let (width, height) = params.grid_size();
+    let limit = params.max_dimension as usize;
+    if !params.allow_huge && (width > limit || height > limit) {
+        return Err(MandelbrotError::DimensionsTooLarge {
+            width,
+            height,
+            limit: params.max_dimension,
+        });
+    }
+    if !(params.gamma > 0.0 && params.gamma.is_finite()) {
+        return Err(MandelbrotError::InvalidGamma(params.gamma));
+    }
+    check_power(params.power)?;
+    // An infinite bailout is fine: orbits then stop at overflow, as `escaped`
+    // allows.
+    for bailout in Some(params.bailout).into_iter().chain(params.smoothing_bailout) {
+        if bailout.is_nan() || bailout <= 0.0 {
+            let message = format!("bailouts must be positive, got {bailout}");
+            return Err(MandelbrotError::InvalidParams(message));
+        }
+    }
+    if !params.palette_offset.is_finite() {
+        let message = format!("palette offset must be finite, got {}", params.palette_offset);
+        return Err(MandelbrotError::InvalidParams(message));
+    }
+    // Caught up front rather than after the render has been paid for.
+    #[cfg(feature = "fs")]
+    if let Some(path) = &params.output {
+        OutputFormat::for_path(path)?;
+    }
+    let max_iter = params.max_iter();
+    if max_iter == 0 {
+        return Err(MandelbrotError::InvalidIterations(max_iter));
+    }
+    if params.interior_budget == Some(0) {
+        return Err(MandelbrotError::InvalidIterations(0));
+    }
+    Ok(())
+}
+
+fn check_power(power: u32) -> Result<(), MandelbrotError> {
+    if power < 2 {
+        let message = format!("multibrot power must be at least 2, got {power}");
+        return Err(MandelbrotError::InvalidParams(message));
+    }
+    Ok(())
+}
+
+fn validate_bounds(
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+    pixel_density: i128,
+) -> Result<(), MandelbrotError> {
+    // Written so that NaN bounds are rejected too.
+    if !(x_min < x_max && y_min < y_max) {
+        return Err(MandelbrotError::InvalidViewport {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        });
+    }
+    if pixel_density <= 0 {
+        return Err(MandelbrotError::InvalidDensity(pixel_density));
+    }
+    Ok(())
+}
+
+// Spans that `pixel_density` covers with less than half a sample would be
+// rounded up to a single row or column by `axis_len`, which at deep zooms
+// quietly turns the whole render into one pixel; renders refuse them and
+// point at `RenderParams::size` instead. Written so that the NaN of
+// an infinite span is rejected too.
+fn validate_samples(
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+    pixel_density: i128,
+) -> Result<(), MandelbrotError> {
+    let width = (x_max - x_min) * pixel_density as f64;
+    let height = (y_max - y_min) * pixel_density as f64;
+    if !(width.round() >= 1.0 && height.round() >= 1.0) {
+        return Err(MandelbrotError::DegenerateGrid { width, height });
+    }
+    Ok(())
+}
+
+// Arbitrary precision is only implemented for the quadratic formulas, so
+// higher Multibrot powers, `Newton` and rotated windows always stay in `f64`.
+fn precise_bits(params: &RenderParams, fractal: Fractal, width: usize, height: usize) -> Option<u32> {
+    if params.power != 2 || fractal == Fractal::Newton || params.rotation != 0.0 {
+        return None;
+    }
+    if width < 2 || height < 2 {
+        return None;
+    }
+    let (spacing, magnitude) = window_scale(params, fractal, width, height);
+    params.precision.bits_for(spacing, magnitude)
+}
+
+// The pixel spacing of a window at least 2 x 2 pixels, and the largest
+// coordinate magnitude involved in iterating it.
+fn window_scale(
+    params: &RenderParams,
+    fractal: Fractal,
+    width: usize,
+    height: usize,
+) -> (f64, f64) {
+    let spacing = ((params.x_max - params.x_min) / (width - 1) as f64)
+        .min((params.y_max - params.y_min) / (height - 1) as f64);
+    let magnitude = [params.x_min, params.x_max, params.y_min, params.y_max]
+        .iter()
+        .fold(0.0f64, |m, v| m.max(v.abs()));
+    let magnitude = match fractal {
+        Fractal::Julia(c) => magnitude.max(c.norm()),
+        _ => magnitude,
+    };
+    (spacing, magnitude)
+}
+
+// Supersampling is not applied here; every pixel is iterated once at its
+// exact fixed-point grid position.
+fn with_precise_shader<R>(
+    params: &RenderParams,
+    fractal: Fractal,
+    (width, height): (usize, usize),
+    bits: u32,
+    f: impl FnOnce(&Shader<&SampleFn>) -> R,
+) -> R {
+    let sampling = params.sampling;
+    let re_axis = precise::fixed_axis(params.x_min, params.x_max, width, bits, sampling);
+    let im_axis = precise::fixed_axis(params.y_min, params.y_max, height, bits, sampling);
+    let dx = params.sampling.spacing(params.x_min, params.x_max, width);
+    let dy = params.sampling.spacing(params.y_min, params.y_max, height);
+    let (z0_re, z0_im) = (to_fixed(params.z0.re, bits), to_fixed(params.z0.im, bits));
+
+    let sample = |i: usize, j: usize, _offset: (f64, f64)| {
+        let (re, im) = (&re_axis[j], &im_axis[i]);
+        let point = Complex::new(
+            params.x_min + j as f64 * dx,
+            params.y_min + i as f64 * dy,
+        );
+        let escape = match fractal {
+            Fractal::Julia(c) => precise::precise_iterate(
+                re.clone(),
+                im.clone(),
+                &to_fixed(c.re, bits),
+                &to_fixed(c.im, bits),
+                params.iteration_budget(),
+                bits,
+                fractal,
+            ),
+            _ => precise::precise_iterate(
+                z0_re.clone(),
+                z0_im.clone(),
+                re,
+                im,
+                params.iteration_budget(),
+                bits,
+                fractal,
+            ),
+        };
+        (point, escape)
+    };
+
+    f(&Shader::new(params, fractal, (width, height), dx.max(dy), 1, &sample))
+}
+
+// Hands each row of raw subpixels to `f` along with its y coordinate, spread
+// across threads when the `parallel` feature is on, and reports each
+// completed row to `progress`. Rows for which `skip` is true are left alone
+// and are not counted.
+fn for_each_row<P, K, F>(image: &mut ImageBuffer<P, Vec<u8>>, progress: Progress, skip: K, f: F)
+where
+    P: Pixel<Subpixel = u8>,
+    K: Fn(usize) -> bool + Sync,
+    F: Fn(usize, &mut [u8]) + Sync,
+{
+    let stride = image.width() as usize * P::CHANNEL_COUNT as usize;
+    if stride == 0 {
+        return;
+    }
+    let total = (0..image.height() as usize).filter(|&y| !skip(y)).count();
+    let completed = Mutex::new((0, progress));
+    let run = |y: usize, row: &mut [u8]| {
+        if skip(y) {
+            return;
+        }
+        f(y, row);
+        let mut completed = completed.lock().unwrap();
+        completed.0 += 1;
+        let done = completed.0;
+        (completed.1)(done, total);
+    };
+
+    #[cfg(feature = "parallel")]
+    image
+        .par_chunks_exact_mut(stride)
+        .enumerate()
+        .for_each(|(y, row)| run(y, row));
+
+    #[cfg(not(feature = "parallel"))]
+    image
+        .chunks_exact_mut(stride)
+        .enumerate()
+        .for_each(|(y, row)| run(y, row));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_yields_exactly_that_many_values() {
+        for limit in 0..5 {
+            assert_eq!(mandelbrot(Complex::new(0.0, 0.0)).limit(limit).build().count(), limit as usize);
+        }
+    }
+
+    #[test]
+    fn limited_orbit_starts_at_z() {
+        let orbit = mandelbrot(Complex::new(1.0, 0.0)).limit(4).build().collect::<Vec<_>>();
+        assert_eq!(orbit, [0.0, 1.0, 2.0, 5.0].map(|re| Complex::new(re, 0.0)));
+        let cubic = mandelbrot(Complex::new(1.0, 0.0)).power(3).unwrap().limit(4).build();
+        assert_eq!(cubic.collect::<Vec<_>>(), [0.0, 1.0, 2.0, 9.0].map(|re| Complex::new(re, 0.0)));
+        for power in [0, 1] {
+            let err = mandelbrot(Complex::new(1.0, 0.0)).power(power).err().unwrap();
+            assert!(matches!(err, MandelbrotError::InvalidParams(_)), "{err}");
+        }
+    }
+
+    // -0.1+0.65i escapes after ~75 iterations and 0.3+0.5i is a member, so
+    // nearby points with unambiguous answers stand in for them.
+    #[test]
+    fn known_members_are_stable() {
+        for (re, im) in [(0.0, 0.0), (-1.0, 0.0), (-0.1, 0.6)] {
+            assert!(is_stable(&Complex::new(re, im), 1000), "{re}+{im}i");
+        }
+    }
+
+    #[test]
+    fn classify_names_the_path_that_decided_membership() {
+        let classify_at = |re, im, max_iter| {
+            classify(&Complex::new(re, im), &EscapeConfig::new(max_iter))
+        };
+        assert_eq!(classify_at(0.0, 0.0, 100), Classification::InsideCardioid);
+        assert_eq!(classify_at(-0.12, 0.75, 500), Classification::PeriodicStable(3));
+        assert_eq!(classify_at(-0.12, 0.75, 5), Classification::ReachedMaxIter);
+        assert_eq!(classify_at(1.0, 0.0, 100), Classification::Escaped(2));
+        let points = complex_matrix(-2.0, 0.5, -1.5, 1.5, 30).unwrap();
+        for c in points.iter() {
+            let brute = Fractal::Mandelbrot.fate(c, &EscapeConfig::new(200));
+            let classified = classify(c, &EscapeConfig::new(200));
+            assert_eq!(classified.is_stable(), brute.is_stable(), "at {c}");
+            assert_eq!(classified.is_stable(), is_stable(c, 200), "at {c}");
+        }
+    }
+
+
+    #[test]
+    fn known_non_members_escape() {
+        for (re, im) in [(1.0, 1.0), (2.0, 0.0), (0.4, 0.5)] {
+            assert!(!is_stable(&Complex::new(re, im), 1000), "{re}+{im}i");
+        }
+    }
+
+    #[test]
+    fn far_points_escape_quickly() {
+        for (re, im) in [(10.0, 0.0), (0.0, -10.0), (3.0, 3.0)] {
+            assert_eq!(escape_time(&Complex::new(re, im), &EscapeConfig::new(1000)), Some(0));
+        }
+        assert!(escape_time(&Complex::new(1.0, 1.0), &EscapeConfig::new(1000)).unwrap() <= 2);
+    }
+
+    // With an infinite bailout the orbit of 1+i overflows: `re^2 - im^2`
+    // becomes `inf - inf`, and a NaN `norm_sqr()` is never above anything.
+    #[test]
+    fn orbits_that_overflow_count_as_escaped() {
+        let c = Complex::new(1.0, 1.0);
+        let config = EscapeConfig::new(1000).with_bailout_sqr(f64::INFINITY);
+        let escape = escape_time(&c, &config).unwrap();
+        assert!(escape < 20);
+        assert_eq!(Fractal::Mandelbrot.escape_time(&c, &config), Some(escape));
+        assert_eq!(escape_times_for(&[c], &config), [Some(escape)]);
+        assert_eq!(classify(&c, &config), Classification::Escaped(escape));
+        assert_eq!(orbit(c, &config).len(), escape + 2);
+        assert_eq!(Fractal::Mandelbrot.smooth_escape_time(&c, &config), escape as f64);
+    }
+
+    #[test]
+    fn fractional_span_rounds_to_nearest_sample_count() {
+        // (0.3 - 0.2) * 30 is 2.999..., which truncation would turn into 2.
+        assert_eq!(complex_matrix(0.2, 0.3, 0.0, 0.1, 30).unwrap().dim(), (3, 3));
+        assert_eq!(complex_matrix(0.0, 0.01, 0.0, 0.01, 10).unwrap().dim(), (1, 1));
+    }
+
+    #[test]
+    fn inverted_viewport_is_rejected() {
+        assert!(matches!(
+            complex_matrix(0.5, -2.0, -1.5, 1.5, 100),
+            Err(MandelbrotError::InvalidViewport { .. })
+        ));
+        assert!(matches!(
+            complex_matrix(-2.0, 0.5, 1.5, 1.5, 100),
+            Err(MandelbrotError::InvalidViewport { .. })
+        ));
+    }
+
+    #[test]
+    fn deep_zoom_at_a_fixed_density_is_refused_rather_than_collapsed() {
+        let params = RenderParams {
+            x_min: -0.75,
+            x_max: -0.75 + 1e-6,
+            y_min: 0.1,
+            y_max: 0.1 + 1e-6,
+            pixel_density: 100_000,
+            ..RenderParams::default()
+        };
+        assert!(matches!(render(&params), Err(MandelbrotError::DegenerateGrid { .. })));
+        let sized = RenderParams { size: Some((4, 4)), ..params };
+        assert_eq!(render(&sized).unwrap().dimensions(), (4, 4));
+    }
+
+    #[test]
+    fn zero_density_size_or_iterations_is_rejected() {
+        let params = RenderParams { pixel_density: 0, ..RenderParams::default() };
+        assert!(matches!(render(&params), Err(MandelbrotError::InvalidDensity(0))));
+        let params = RenderParams { size: Some((0, 10)), ..RenderParams::default() };
+        assert!(matches!(render(&params), Err(MandelbrotError::InvalidSize { .. })));
+        let params = RenderParams {
+            pixel_density: 10,
+            iterations: IterationPolicy::AutoZoom { base: 0 },
+            ..RenderParams::default()
+        };
+        assert!(matches!(render(&params), Err(MandelbrotError::InvalidIterations(0))));
+    }
+
+    #[test]
+    fn preview_keeps_the_viewport_at_a_tenth_of_the_size() {
+        let params = RenderParams {
+            iterations: IterationPolicy::Fixed(1000),
+            samples_per_pixel: 3,
+            ..RenderParams::default()
+        };
+        let preview = params.preview();
+        let (width, height) = params.grid_size();
+        assert_eq!(preview.grid_size(), (width / 10, height / 10));
+        assert_eq!((preview.x_min, preview.x_max), (params.x_min, params.x_max));
+        assert_eq!((preview.y_min, preview.y_max), (params.y_min, params.y_max));
+        assert_eq!(preview.max_iter(), PREVIEW_MAX_ITER);
+        assert_eq!(preview.samples_per_pixel, 1);
+    }
+
+    #[test]
+    fn period_mode_colors_each_component_by_its_cycle_length() {
+        let params = RenderParams {
+            interior_mode: InteriorMode::Period,
+            iterations: IterationPolicy::Fixed(500),
+            ..RenderParams::default()
+        };
+        let (fractal, config) = (Fractal::Mandelbrot, EscapeConfig::new(500).with_bailout_sqr(4.0));
+        // The main cardioid, the period-2 bulb and the period-3 bulb on top.
+        assert_eq!(fractal.period(&Complex::new(0.0, 0.0), &config), Some(1));
+        assert_eq!(fractal.period(&Complex::new(-1.0, 0.0), &config), Some(2));
+        assert_eq!(fractal.period(&Complex::new(-0.12, 0.75), &config), Some(3));
+        assert_eq!(fractal.period(&Complex::new(1.0, 0.0), &config), None);
+
+        let shader = Shader::new(&params, fractal, (1, 1), 1.0, 1, |_, _, _| unreachable!());
+        assert_ne!(
+            shader.interior_color(&Complex::new(0.0, 0.0)),
+            shader.interior_color(&Complex::new(-1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn half_open_halves_stitch_into_the_whole() {
+        let region = |x_min: f64, x_max: f64, width: u32| {
+            let params = RenderParams {
+                x_min,
+                x_max,
+                size: Some((width, 24)),
+                sampling: Sampling::HalfOpen,
+                iterations: IterationPolicy::Fixed(50),
+                ..RenderParams::default()
+            };
+            render(&params).unwrap()
+        };
+        let whole = region(-2.0, 0.5, 20);
+        let (left, right) = (region(-2.0, -0.75, 10), region(-0.75, 0.5, 10));
+        for (x, y, pixel) in whole.enumerate_pixels() {
+            let half = if x < 10 { left.get_pixel(x, y) } else { right.get_pixel(x - 10, y) };
+            assert_eq!(pixel, half, "at ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn orbit_stops_at_the_first_escaped_value() {
+        let escaping = orbit(Complex::new(1.0, 0.0), &EscapeConfig::new(100));
+        assert_eq!(escaping, [0.0, 1.0, 2.0, 5.0].map(|re| Complex::new(re, 0.0)));
+        assert_eq!(orbit(Complex::new(-1.0, 0.0), &EscapeConfig::new(10)).len(), 11);
+    }
+
+    #[test]
+    fn batched_escape_times_match_the_scalar_loop() {
+        let points = complex_matrix(-2.0, 0.5, -1.5, 1.5, 40).unwrap();
+        let points = points.iter().copied().collect::<Vec<_>>();
+        let scalar = points
+            .iter()
+            .map(|c| escape_time(c, &EscapeConfig::new(200)))
+            .collect::<Vec<_>>();
+        for lanes in [Lanes::Two, Lanes::Four, Lanes::Eight] {
+            assert_eq!(escape_times(&points, &EscapeConfig::new(200), lanes), scalar);
+        }
+    }
+
+    #[test]
+    fn escape_times_for_takes_points_in_any_order() {
+        let points = [
+            Complex::new(0.3, 0.5),
+            Complex::new(-1.0, 0.0),
+            Complex::new(2.0, 2.0),
+            Complex::new(-0.75, 0.1),
+        ];
+        let expected = points
+            .iter()
+            .map(|c| escape_time(c, &EscapeConfig::new(100)))
+            .collect::<Vec<_>>();
+        assert_eq!(escape_times_for(&points, &EscapeConfig::new(100)), expected);
+        assert!(escape_times_for(&[], &EscapeConfig::new(100)).is_empty());
+    }
+
+    #[test]
+    fn transparent_exterior_only_clears_escaped_pixels() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(50),
+            exterior: Exterior::Transparent,
+            ..RenderParams::default()
+        };
+        let image = render_rgba_image(&params).unwrap();
+        let (width, height) = image.dimensions();
+        // The far left edge is outside the set; the middle row near -0.2 is inside.
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+        assert_eq!(image.get_pixel(width * 7 / 10, height / 2).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn distance_estimate_brackets_the_true_distance() {
+        assert_eq!(distance_estimate(&Complex::new(0.0, 0.0), &EscapeConfig::new(100)), None);
+        // The set's rightmost point is 0.25, so 1+0i is 0.75 away.
+        let estimate = distance_estimate(&Complex::new(1.0, 0.0), &EscapeConfig::new(100)).unwrap();
+        assert!((0.375..=1.5).contains(&estimate), "{estimate}");
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn save_picks_the_encoder_from_the_extension() {
+        let image = RgbImage::from_pixel(4, 3, Rgb([10, 200, 30]));
+        use image::ImageFormat;
+        for (extension, format) in [("jpg", ImageFormat::Jpeg), ("BMP", ImageFormat::Bmp)] {
+            let path = std::env::temp_dir().join(format!("mandelbrot-save.{extension}"));
+            save(&image, &path).unwrap();
+            let reader = image::io::Reader::open(&path).unwrap().with_guessed_format().unwrap();
+            assert_eq!(reader.format(), Some(format));
+            assert_eq!(reader.into_dimensions().unwrap(), (4, 3));
+            std::fs::remove_file(&path).unwrap();
+        }
+        let path = std::env::temp_dir().join("mandelbrot-save.tiff");
+        assert!(matches!(
+            save(&image, &path),
+            Err(MandelbrotError::UnsupportedFormat(extension)) if extension == "tiff"
+        ));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn params_round_trip_through_png_metadata() {
+        let params = RenderParams {
+            x_min: -0.75,
+            x_max: -0.7,
+            y_min: 0.1,
+            y_max: 0.1 + 1.0 / 30.0,
+            pixel_density: 600,
+            iterations: IterationPolicy::Fixed(321),
+            ..RenderParams::default()
+        };
+        let path = std::env::temp_dir().join("mandelbrot-params-round-trip.png");
+        let image = RgbImage::new(2, 2);
+        save_with_params(&image, &params, &path, OutputFormat::Png).unwrap();
+        let read = read_params(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            (read.x_min, read.x_max, read.y_min, read.y_max),
+            (params.x_min, params.x_max, params.y_min, params.y_max)
+        );
+        assert_eq!(read.pixel_density, params.pixel_density);
+        assert_eq!(read.iterations, params.iterations);
+    }
+
+    #[test]
+    fn builder_defaults_match_render_params_defaults() {
+        let built = RenderParams::builder().density(40).build();
+        let literal = RenderParams {
+            pixel_density: 40,
+            ..RenderParams::default()
+        };
+        assert_eq!(render(&built).unwrap(), render(&literal).unwrap());
+    }
+
+    #[test]
+    fn mirrored_rows_match_shading_every_row() {
+        for height in [203, 480, 481, 555] {
+            let params = RenderParams {
+                x_min: -2.1,
+                x_max: 0.7,
+                y_min: -1.23456789,
+                y_max: 1.23456789,
+                size: Some((48, height)),
+                iterations: IterationPolicy::Fixed(200),
+                color_mode: ColorMode::Smooth,
+                samples_per_pixel: 2,
+                ..RenderParams::default()
+            };
+            let im = params.sampling.axis(params.y_min, params.y_max, height as usize);
+            let mirrored: RgbImage = with_fractal_shader(&params, params.fractal, |shader| {
+                let copied = (0..shader.height).filter_map(|y| Some((y, shader.mirror_of(y)?)));
+                let copied = copied.collect::<Vec<_>>();
+                assert!(!copied.is_empty());
+                // Rows are only copied where the true samples are negated bit for bit.
+                for (y, m) in copied {
+                    let h = shader.height;
+                    assert_eq!(im[h - 1 - y], -im[h - 1 - m], "row {y} of {height}");
+                }
+                shader.rows(0..shader.height, &mut |_, _| {})
+            });
+            let full: RgbImage = with_mirrored_shader(&params, params.fractal, false, |shader| {
+                assert!(shader.mirror.is_empty());
+                shader.rows(0..shader.height, &mut |_, _| {})
+            });
+            assert_eq!(mirrored, full, "height {height}");
+        }
+    }
+
+    #[test]
+    fn render_rgba_returns_four_bytes_per_pixel() {
+        let params = RenderParams {
+            pixel_density: 20,
+            ..RenderParams::default()
+        };
+        let (width, height) = params.grid_size();
+        let bytes = render_rgba(&params).unwrap();
+        assert_eq!(bytes.len(), width * height * 4);
+        assert_eq!(bytes, render_rgba_image(&params).unwrap().into_raw());
+    }
+
+    #[test]
+    fn render_stats_count_every_sample() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(50),
+            ..RenderParams::default()
+        };
+        let (image, stats) = render_with_stats(&params).unwrap();
+        assert_eq!(image, render(&params).unwrap());
+        let (width, height) = params.grid_size();
+        assert_eq!(stats.total_pixels, (width * height) as u64);
+        assert_eq!(stats.interior_pixels + stats.escaped_pixels, stats.total_pixels);
+        assert!(stats.interior_pixels > 0 && stats.escaped_pixels > 0);
+        assert!((1..=50).contains(&stats.max_orbit_length));
+    }
+
+    #[test]
+    #[cfg(feature = "chart")]
+    fn grid_overlay_draws_the_axes() {
+        let params = RenderParams {
+            x_min: -1.0,
+            x_max: 1.0,
+            y_min: -1.0,
+            y_max: 1.0,
+            size: Some((21, 21)),
+            ..RenderParams::default()
+        };
+        let mut image = RgbImage::new(21, 21);
+        let grid = GridOverlay {
+            re_step: 1.0,
+            im_step: 1.0,
+            color: Rgb([200, 0, 0]),
+            labels: false,
+        };
+        draw_grid(&mut image, &params, &grid).unwrap();
+        assert_eq!(image[(10, 3)], Rgb([200, 0, 0]));
+        assert_eq!(image[(3, 10)], Rgb([200, 0, 0]));
+        assert_eq!(image[(3, 3)], Rgb([0, 0, 0]));
+        for step in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let bad = GridOverlay {
+                im_step: step,
+                ..grid
+            };
+            let result = draw_grid(&mut image, &params, &bad);
+            assert!(matches!(result, Err(MandelbrotError::InvalidParams(_))));
+        }
+        let rotated = RenderParams { rotation: 0.3, ..params };
+        let result = draw_grid(&mut image, &rotated, &grid);
+        assert!(matches!(result, Err(MandelbrotError::InvalidParams(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "chart")]
+    fn legend_runs_the_palette_up_a_margin() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(40),
+            palette: Palette::fire(),
+            ..RenderParams::default()
+        };
+        let image = render(&params).unwrap();
+        let legend = Legend::default();
+        let keyed = with_legend(&image, &params, &legend).unwrap();
+        let (width, height) = image.dimensions();
+        assert_eq!(keyed.dimensions(), (width + legend.margin, height));
+        assert!(image.enumerate_pixels().all(|(x, y, pixel)| keyed[(x, y)] == *pixel));
+        let bar = width + legend.margin / 8;
+        assert_eq!(keyed[(bar, height - 1)], params.palette.color(0.0));
+        assert_eq!(keyed[(bar, 0)], params.palette.color(1.0));
+        assert_eq!(keyed[(width + legend.margin - 1, height / 2)], legend.background);
+    }
+
+    #[test]
+    fn tiled_renders_match_row_renders() {
+        let params = RenderParams {
+            pixel_density: 30,
+            iterations: IterationPolicy::Fixed(60),
+            samples_per_pixel: 2,
+            ..RenderParams::default()
+        };
+        for tile_size in [1, 7, DEFAULT_TILE_SIZE] {
+            let tiled = RenderParams { tile_size: Some(tile_size), ..params.clone() };
+            assert_eq!(render(&tiled).unwrap(), render(&params).unwrap());
+            assert_eq!(render_rgba_image(&tiled).unwrap(), render_rgba_image(&params).unwrap());
+        }
+    }
+
+    #[test]
+    fn render_into_reuses_a_matching_buffer() {
+        let params = RenderParams {
+            pixel_density: 20,
+            ..RenderParams::default()
+        };
+        let (width, height) = params.grid_size();
+        let mut image = RgbImage::new(width as u32, height as u32);
+        render_into(&params, &mut image).unwrap();
+        assert_eq!(image, render(&params).unwrap());
+
+        let mut wrong = RgbImage::new(width as u32 + 1, height as u32);
+        assert!(matches!(
+            render_into(&params, &mut wrong),
+            Err(MandelbrotError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn palette_blends_in_the_chosen_color_space() {
+        let red_blue = vec![(0.0, Rgb([255, 0, 0])), (1.0, Rgb([0, 0, 255]))];
+        let rgb = Palette::new(red_blue.clone());
+        assert_eq!(rgb.color(0.5), Rgb([128, 0, 128]));
+        let hsl = Palette::new(red_blue).with_space(ColorSpace::Hsl);
+        assert_eq!(hsl.color(0.5), Rgb([255, 0, 255]));
+
+        let lab = Palette::grayscale().with_space(ColorSpace::Lab);
+        assert_eq!(lab.color(0.0), Rgb([0, 0, 0]));
+        assert_eq!(lab.color(1.0), Rgb([255, 255, 255]));
+        // L* = 50 is sRGB 119, darker than the RGB midpoint.
+        assert_eq!(lab.color(0.5), Rgb([119, 119, 119]));
+    }
+
+    #[test]
+    fn log_scale_keeps_the_ends_and_lifts_low_counts() {
+        assert_eq!(ColorScale::Log.apply(0.0, 100), 0.0);
+        assert!((ColorScale::Log.apply(1.0, 100) - 1.0).abs() < 1e-12);
+        assert!(ColorScale::Log.apply(0.05, 100) > 0.35);
+        assert_eq!(ColorScale::Linear.apply(0.05, 100), 0.05);
+    }
+
+    #[test]
+    fn seeded_buddhabrot_is_reproducible() {
+        let params = RenderParams {
+            pixel_density: 20,
+            seed: Some(7),
+            ..RenderParams::default()
+        };
+        let first = buddhabrot_histogram(100_000, 50, &params).unwrap();
+        assert_eq!(first, buddhabrot_histogram(100_000, 50, &params).unwrap());
+        assert!(first.iter().any(|&hits| hits > 0));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn npy_round_trips_escape_counts() {
+        let c = complex_matrix(-2.0, 0.5, -1.0, 1.0, 4).unwrap();
+        let counts = escape_counts(&c, 30, Fractal::Mandelbrot);
+        let path = std::env::temp_dir().join("mandelbrot-escape-counts.npy");
+        write_npy(&counts, &path).unwrap();
+        let read: Array2<u32> = ndarray_npy::read_npy(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, counts);
+        assert!(counts.iter().any(|&n| n == 30));
+    }
+
+    #[test]
+    fn perturbation_matches_arbitrary_precision_at_depth() {
+        let (re, im, r) = (-0.743643887037151, 0.131825904205330, 1e-11);
+        let params = RenderParams {
+            x_min: re - r,
+            x_max: re + r,
+            y_min: im - r,
+            y_max: im + r,
+            size: Some((24, 24)),
+            iterations: IterationPolicy::Fixed(250),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let arbitrary = render(&RenderParams { precision: Precision::Auto, ..params.clone() });
+        let perturbed = render(&RenderParams { precision: Precision::Perturbation, ..params });
+        assert_eq!(arbitrary.unwrap(), perturbed.unwrap());
+    }
+
+    #[test]
+    fn gamma_brightens_midtones_and_keeps_the_ends() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(50),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let linear = render(&params).unwrap();
+        let corrected = render(&RenderParams { gamma: 2.2, ..params.clone() }).unwrap();
+        for (a, b) in linear.pixels().zip(corrected.pixels()) {
+            assert!(b.0[0] >= a.0[0]);
+            if a.0[0] == 0 || a.0[0] == 255 {
+                assert_eq!(a, b);
+            }
+        }
+        assert!(linear.pixels().zip(corrected.pixels()).any(|(a, b)| b.0[0] > a.0[0]));
+        let params = RenderParams { gamma: 0.0, ..params };
+        assert!(matches!(render(&params), Err(MandelbrotError::InvalidGamma(_))));
+    }
+
+    #[test]
+    fn tricorn_has_three_fold_symmetry() {
+        let turn = Complex::from_polar(1.0, 2.0 * std::f64::consts::PI / 3.0);
+        for c in [Complex::new(-1.5, 0.2), Complex::new(0.5, 0.5), Complex::new(0.2, -0.9)] {
+            let escape = tricorn_escape_time(&c, &EscapeConfig::new(100));
+            assert!(escape.is_some());
+            assert_eq!(tricorn_escape_time(&(c * turn), &EscapeConfig::new(100)), escape);
+            assert_eq!(tricorn_escape_time(&c.conj(), &EscapeConfig::new(100)), escape);
+        }
+        // -1 -> 0 -> -1 is a 2-cycle.
+        assert_eq!(tricorn_escape_time(&Complex::new(-1.0, 0.0), &EscapeConfig::new(100)), None);
+    }
+
+    #[test]
+    fn auto_zoom_iterations_grow_with_zoom() {
+        let iterations = IterationPolicy::AutoZoom { base: 20 };
+        let full = RenderParams {
+            iterations,
+            ..RenderParams::default()
+        };
+        assert_eq!(full.max_iter(), 20);
+        let deep = full.with_viewport(&Viewport::new(Complex::new(-0.75, 0.1), 1024.0, 8, 8));
+        assert_eq!(deep.max_iter(), 220);
+        assert_eq!(IterationPolicy::Fixed(20).max_iter(1024.0), 20);
+    }
+
+    #[test]
+    fn julia_orbit_starts_from_a_complex_point() {
+        let orbit = julia(Complex::new(0.0, 1.0), Complex::new(-0.5, 0.25))
+            .limit(2)
+            .build()
+            .collect::<Vec<_>>();
+        assert_eq!(orbit, [Complex::new(0.0, 1.0), Complex::new(-1.5, 0.25)]);
+    }
+
+    #[test]
+    fn subregion_keeps_density_and_the_whole_view() {
+        let view = Viewport::new(Complex::new(-0.75, 0.0), 1.0, 300, 240);
+        assert_eq!(view.subregion(0.0, 0.0, 1.0, 1.0).bounds(), view.bounds());
+
+        let left_third = view.subregion(0.0, 0.0, 1.0 / 3.0, 1.0);
+        assert_eq!((left_third.width, left_third.height), (100, 240));
+        let (x_min, x_max, y_min, y_max) = view.bounds();
+        let (sub_x_min, sub_x_max, sub_y_min, sub_y_max) = left_third.bounds();
+        assert!((sub_x_min - x_min).abs() < 1e-12);
+        assert!((sub_x_max - (x_min + (x_max - x_min) / 3.0)).abs() < 1e-12);
+        assert_eq!((sub_y_min, sub_y_max), (y_min, y_max));
+    }
+
+    #[test]
+    fn gray16_scales_escape_iterations_over_the_limit() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(100),
+            ..RenderParams::default()
+        };
+        let image = render_gray16(&params).unwrap();
+        let (width, height) = image.dimensions();
+        assert_eq!(image.get_pixel(width * 7 / 10, height / 2).0, [u16::MAX]);
+        // The top-left corner, -2+1.5i, escapes on the first iteration.
+        assert_eq!(image.get_pixel(0, 0).0, [0]);
+    }
+
+    #[test]
+    fn membership_grid_keeps_the_grid_shape() {
+        let c = complex_matrix(-2.0, 0.5, -1.5, 1.5, 20).unwrap();
+        let grid = membership_grid(&c, 50, Fractal::Mandelbrot);
+        assert_eq!(grid.dim(), c.dim());
+        let members = grid.iter().filter(|&&member| member).count();
+        assert_eq!(members, get_members(&c, 50, Fractal::Mandelbrot).len());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn checkpointed_render_matches_render_and_cleans_up() {
+        let params = RenderParams {
+            pixel_density: 40,
+            iterations: IterationPolicy::Fixed(50),
+            ..RenderParams::default()
+        };
+        let path = std::env::temp_dir().join("mandelbrot-checkpoint-matches.ckpt");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(render_checkpointed(&params, &path).unwrap(), render(&params).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn foreign_checkpoint_is_rejected() {
+        let path = std::env::temp_dir().join("mandelbrot-checkpoint-foreign.ckpt");
+        std::fs::write(&path, vec![b'x'; 4096]).unwrap();
+        let params = RenderParams {
+            pixel_density: 10,
+            ..RenderParams::default()
+        };
+        let result = render_checkpointed(&params, &path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(MandelbrotError::Checkpoint(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn checkpoints_are_keyed_on_the_pixel_settings_only() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(40),
+            ..RenderParams::default()
+        };
+        let (width, height) = params.grid_size();
+        let path = std::env::temp_dir().join("mandelbrot-checkpoint-keyed.ckpt");
+
+        std::fs::write(&path, checkpoint::header(&params, width, height)).unwrap();
+        let resumed = RenderParams { max_dimension: 1 << 14, threads: Some(1), ..params.clone() };
+        assert_eq!(render_checkpointed(&resumed, &path).unwrap(), render(&params).unwrap());
+
+        let recolored = RenderParams { palette: Palette::fire(), ..params.clone() };
+        std::fs::write(&path, checkpoint::header(&recolored, width, height)).unwrap();
+        let result = render_checkpointed(&params, &path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(MandelbrotError::Checkpoint(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn random_checkpoints_resume_only_with_a_seed() {
+        let path = std::env::temp_dir().join("mandelbrot-checkpoint-seeded.ckpt");
+        let _ = std::fs::remove_file(&path);
+        let jittered = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(40),
+            jitter: true,
+            samples_per_pixel: 2,
+            ..RenderParams::default()
+        };
+        let dithered = RenderParams { jitter: false, dither: Dither::Random, ..jittered.clone() };
+        for params in [jittered, dithered] {
+            let result = render_checkpointed(&params, &path);
+            assert!(matches!(result, Err(MandelbrotError::Checkpoint(_))), "{params}");
+            assert!(!path.exists());
+
+            // A seeded render resumes from a checkpoint holding only its header.
+            let seeded = RenderParams { seed: Some(7), ..params };
+            let (width, height) = seeded.grid_size();
+            std::fs::write(&path, checkpoint::header(&seeded, width, height)).unwrap();
+            assert_eq!(render_checkpointed(&seeded, &path).unwrap(), render(&seeded).unwrap());
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chart")]
+    fn plotted_members_cover_their_pixels_at_any_density() {
+        // A window inside the main cardioid, sampled far more coarsely than
+        // the image resolution.
+        let (x_range, y_range) = (-0.4..0.0, -0.2..0.2);
+        let members = get_members(&complex_matrix(-0.4, 0.0, -0.2, 0.2, 10).unwrap(), 50,
+            Fractal::Mandelbrot);
+        let path = std::env::temp_dir().join("mandelbrot-plot-coverage.png");
+        let style = PlotStyle::default();
+        plot_mandelbrot(members, x_range, y_range, 10, 400, &style, &path).unwrap();
+        let image = image::open(&path).unwrap().to_rgb8();
+        std::fs::remove_file(&path).unwrap();
+        let (width, height) = image.dimensions();
+        for x in width / 3..2 * width / 3 {
+            assert_eq!(*image.get_pixel(x, height / 2), style.interior);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chart")]
+    fn contact_sheet_tiles_each_render_into_its_cell() {
+        let sweep = [50, 100, 150]
+            .map(|iterations| RenderParams {
+                size: Some((40, 30)),
+                iterations: IterationPolicy::Fixed(iterations),
+                palette: Palette::fire(),
+                ..RenderParams::default()
+            })
+            .to_vec();
+        let sheet = render_grid(&sweep, 2).unwrap();
+        let gap = SHEET_GAP;
+        assert_eq!(sheet.dimensions(), (2 * 40 + 3 * gap, 2 * 30 + 3 * gap));
+        // The bottom-right quarter of each cell is clear of its label.
+        for (k, params) in sweep.iter().enumerate() {
+            let image = render(params).unwrap();
+            let (x0, y0) = (gap + (k as u32 % 2) * (40 + gap), gap + (k as u32 / 2) * (30 + gap));
+            for (x, y) in [(20, 15), (39, 29), (25, 20)] {
+                assert_eq!(sheet[(x0 + x, y0 + y)], image[(x, y)]);
+            }
+        }
+        assert_eq!(sheet[(gap + 40 + gap + 20, gap + 30 + gap + 15)], Rgb([0, 0, 0]));
+        let err = render_grid(&sweep, 0).unwrap_err();
+        assert!(matches!(err, MandelbrotError::InvalidParams(_)), "{err}");
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn zoom_frames_match_their_single_renders_whatever_order_they_finish() {
+        let params = RenderParams {
+            size: Some((24, 16)),
+            iterations: IterationPolicy::Fixed(60),
+            threads: Some(2),
+            ..RenderParams::default()
+        };
+        let target = Complex::new(-0.75, 0.1);
+        let dir = std::env::temp_dir().join("mandelbrot-zoom-frames");
+        render_zoom_sequence(&params, target, 1.0, 8.0, 4, &dir).unwrap();
+        for (frame, zoom) in [1.0, 2.0, 4.0, 8.0].into_iter().enumerate() {
+            let path = dir.join(format!("frame_{frame:04}.png"));
+            let saved = image::open(&path).unwrap().to_rgb8();
+            let viewport = Viewport::new(target, zoom, 24, 16);
+            assert_eq!(saved, render(&params.clone().with_viewport(&viewport)).unwrap());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "chart")]
+    fn plot_caption_names_the_fractal_or_is_left_out() {
+        assert_eq!(PlotCaption::for_fractal(Fractal::BurningShip).text, "Burning Ship");
+        let members = vec![(0.0, 0.0)];
+        let path = std::env::temp_dir().join("mandelbrot-plot-caption.png");
+        let plot_height = |style: &PlotStyle| {
+            plot_mandelbrot(members.clone(), -1.0..1.0, -1.0..1.0, 10, 200, style, &path).unwrap();
+            let height = image::open(&path).unwrap().height();
+            std::fs::remove_file(&path).unwrap();
+            height
+        };
+        let captioned = plot_height(&PlotStyle::default());
+        let bare = plot_height(&PlotStyle { caption: None, ..PlotStyle::default() });
+        assert_eq!(captioned - bare, PLOT_CAPTION_SIZE + 10);
+    }
+
+    #[test]
+    fn newton_colors_each_basin_by_its_root() {
+        let params = RenderParams {
+            x_min: -1.5,
+            x_max: 1.5,
+            y_min: -1.5,
+            y_max: 1.5,
+            pixel_density: 10,
+            fractal: Fractal::Newton,
+            ..RenderParams::default()
+        };
+        let image = render(&params).unwrap();
+        let (width, height) = image.dimensions();
+        let pixel_at = |re: f64, im: f64| {
+            let x = ((re + 1.5) / 3.0 * (width - 1) as f64).round() as u32;
+            let y = ((1.5 - im) / 3.0 * (height - 1) as f64).round() as u32;
+            *image.get_pixel(x, y)
+        };
+        for (root, color) in NEWTON_ROOTS.iter().zip(NEWTON_COLORS) {
+            assert_eq!(pixel_at(root.re, root.im), color);
+        }
+
+        let shaded = render(&RenderParams { newton_shading: true, ..params }).unwrap();
+        let [r, _, _] = shaded.get_pixel(width - 1, height / 2).0;
+        assert!(r < NEWTON_COLORS[0].0[0]);
+    }
+
+    #[test]
+    fn oversized_renders_need_allow_huge() {
+        let params = RenderParams {
+            size: Some((MAX_DIMENSION + 1, 1)),
+            iterations: IterationPolicy::Fixed(1),
+            ..RenderParams::default()
+        };
+        assert!(matches!(
+            render(&params),
+            Err(MandelbrotError::DimensionsTooLarge { width, height: 1, limit: MAX_DIMENSION })
+                if width == MAX_DIMENSION as usize + 1
+        ));
+        assert!(render(&RenderParams { allow_huge: true, ..params.clone() }).is_ok());
+        assert!(render(&RenderParams { max_dimension: 10_000, ..params }).is_ok());
+    }
+
+    #[test]
+    fn adaptive_sampling_supersamples_only_the_edge() {
+        let params = RenderParams {
+            pixel_density: 40,
+            iterations: IterationPolicy::Fixed(50),
+            samples_per_pixel: 3,
+            ..RenderParams::default()
+        };
+        let (uniform, uniform_stats) = render_with_stats(&params).unwrap();
+        let single = render(&RenderParams { samples_per_pixel: 1, ..params.clone() }).unwrap();
+        let adaptive = RenderParams { adaptive_sampling: true, ..params };
+        let (image, stats) = render_with_stats(&adaptive).unwrap();
+        assert!(stats.total_pixels < uniform_stats.total_pixels / 3);
+
+        let (width, height) = image.dimensions();
+        let member = |x: u32, y: u32| single.get_pixel(x, y) == &adaptive.interior;
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let neighbours =
+                [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            let edge = neighbours
+                .iter()
+                .any(|&(nx, ny)| nx < width && ny < height && member(nx, ny) != member(x, y));
+            let expected = if edge { &uniform } else { &single };
+            assert_eq!(pixel, expected.get_pixel(x, y));
+        }
+    }
+
+    #[test]
+    fn member_iter_streams_what_get_members_collects() {
+        let matrix = complex_matrix(-2.0, 0.5, -1.5, 1.5, 20).unwrap();
+        let collected = get_members(&matrix, 30, Fractal::Mandelbrot);
+        let streamed = MemberIter::new(-2.0, 0.5, -1.5, 1.5, 20, 30, Fractal::Mandelbrot).unwrap();
+        assert_eq!(streamed.collect::<Vec<_>>(), collected);
+    }
+
+    #[test]
+    fn f32_grid_matches_the_f64_grid_away_from_the_boundary() {
+        let wide = complex_matrix(-2.0, 0.5, -1.5, 1.5, 20).unwrap();
+        let narrow = complex_matrix(-2.0f32, 0.5, -1.5, 1.5, 20).unwrap();
+        assert_eq!(narrow.dim(), wide.dim());
+        let disagreements = wide
+            .iter()
+            .zip(narrow.iter())
+            .filter(|(w, n)| {
+                escape_time(*w, &EscapeConfig::new(50)) != escape_time(*n, &EscapeConfig::new(50))
+            })
+            .count();
+        assert!(disagreements * 100 < wide.len(), "{disagreements} of {}", wide.len());
+    }
+
+    #[test]
+    fn composite_darkens_the_smooth_color_by_the_trap_distance() {
+        let trap = Trap::Point(Complex::new(0.0, 0.0));
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(50),
+            palette: Palette::fire(),
+            color_mode: ColorMode::Composite { trap },
+            ..RenderParams::default()
+        };
+        let composite = render(&params).unwrap();
+        let smooth = render(&RenderParams { color_mode: ColorMode::Smooth, ..params.clone() });
+        let smooth = smooth.unwrap();
+        let config = params.escape_config();
+        let (width, height) = params.grid_size();
+        let re = params.sampling.axis(params.x_min, params.x_max, width);
+        let im = params.sampling.axis(params.y_min, params.y_max, height);
+        for (x, y, pixel) in composite.enumerate_pixels() {
+            let point = Complex::new(re[x as usize], im[height - 1 - y as usize]);
+            if Fractal::Mandelbrot.escape_time(&point, &config).is_none() {
+                continue;
+            }
+            let distance = Fractal::Mandelbrot.trap_distance(&point, &config, trap);
+            let brightness = 1.0 - (distance / TRAP_RANGE).min(1.0);
+            let expected = smooth[(x, y)].0.map(|c| (c as f64 * brightness).round() as u8);
+            assert_eq!(pixel.0, expected, "pixel ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn potential_bands_wrap_every_band_of_iterations() {
+        let fractal = Fractal::Mandelbrot;
+        let z = Complex::new(3.0, 0.0);
+        let level = fractal.potential_level((5, z), 2);
+        assert!((level - (5.0 - 3f64.ln().ln() / 2f64.ln())).abs() < 1e-12);
+        // A deep escape would overflow `2^n` if it were evaluated directly.
+        assert!(fractal.potential_level((5000, z), 2).is_finite());
+
+        let params = RenderParams {
+            pixel_density: 40,
+            iterations: IterationPolicy::Fixed(100),
+            color_mode: ColorMode::Potential,
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let image = render(&params).unwrap();
+        let distinct: std::collections::HashSet<_> = image.pixels().collect();
+        assert!(distinct.len() > 50);
+    }
+
+    #[test]
+    fn checkerboard_shows_through_a_transparent_exterior() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(50),
+            exterior: Exterior::Transparent,
+            preview_background: PreviewBackground::Checkerboard,
+            ..RenderParams::default()
+        };
+        let image = render(&params).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [CHECKER_LIGHT; 3]);
+        assert_eq!(image.get_pixel(CHECKER_SIZE, 0).0, [CHECKER_DARK; 3]);
+        assert_eq!(image.get_pixel(CHECKER_SIZE, CHECKER_SIZE).0, [CHECKER_LIGHT; 3]);
+
+        let rgba = render_rgba_image(&params).unwrap();
+        assert_eq!(rgba.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn escape_config_carries_power_and_bailout_to_every_loop() {
+        let points = complex_matrix(-1.5, 1.5, -1.5, 1.5, 10).unwrap();
+        let points = points.iter().copied().collect::<Vec<_>>();
+        let cubic = EscapeConfig::new(50).with_power(3);
+        let wide = EscapeConfig::new(50).with_bailout_sqr(1e6);
+        for c in &points {
+            assert_eq!(escape_time(c, &cubic), multibrot_escape_time(c, &cubic), "at {c}");
+            assert_eq!(escape_time(c, &wide), Fractal::Mandelbrot.escape_time(c, &wide), "at {c}");
+            if let (Some(near), Some(far)) =
+                (escape_time(c, &EscapeConfig::new(50)), escape_time(c, &wide))
+            {
+                assert!(far >= near, "at {c}");
+            }
+        }
+        let scalar = points.iter().map(|c| escape_time(c, &cubic)).collect::<Vec<_>>();
+        assert_eq!(escape_times_for(&points, &cubic), scalar);
+    }
+
+    #[test]
+    fn jitter_is_reproducible_from_the_seed() {
+        let params = RenderParams {
+            pixel_density: 40,
+            iterations: IterationPolicy::Fixed(50),
+            samples_per_pixel: 3,
+            jitter: true,
+            seed: Some(7),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let image = render(&params).unwrap();
+        let single_thread = RenderParams { threads: Some(1), ..params.clone() };
+        assert_eq!(render(&single_thread).unwrap(), image);
+        assert_ne!(render(&RenderParams { seed: Some(8), ..params.clone() }).unwrap(), image);
+        assert_ne!(render(&RenderParams { jitter: false, ..params }).unwrap(), image);
+    }
+
+    #[test]
+    fn escape_time_grid_confirms_the_f64_verdicts_in_fixed_point() {
+        let c = complex_matrix(-2.0, 0.5, -1.25, 1.25, 20).unwrap();
+        let hybrid = escape_time_grid(&c, 200, 64);
+        assert_eq!(hybrid, escape_grid(&c, 200, Fractal::Mandelbrot));
+        let point = Array2::from_elem((1, 1), Complex::new(-0.75, 0.01));
+        let exact = precise_escape_time(&to_fixed(-0.75, 64), &to_fixed(0.01, 64), 1000, 64);
+        assert_eq!(escape_time_grid(&point, 1000, 64)[[0, 0]], exact);
+    }
+
+    #[test]
+    fn outline_keeps_only_members_next_to_the_exterior() {
+        let disk = Array2::from_shape_fn((7, 7), |(i, j)| {
+            (i as i32 - 3).pow(2) + (j as i32 - 3).pow(2) <= 4
+        });
+        let edges = boundary_grid(&disk);
+        assert!(edges[[3, 1]] && edges[[1, 3]] && edges[[2, 2]]);
+        assert!(!edges[[3, 3]] && !edges[[3, 2]] && !edges[[0, 0]]);
+        assert!(!boundary_grid(&Array2::from_elem((3, 3), true)).iter().any(|&edge| edge));
+
+        let params = RenderParams { pixel_density: 30, ..RenderParams::default() };
+        let outline = render_outline(&params).unwrap();
+        let (width, height) = params.grid_size();
+        assert_eq!(outline.dimensions(), (width as u32, height as u32));
+        let lines = outline.pixels().filter(|&&pixel| pixel == params.interior).count();
+        let filled = render(&params).unwrap();
+        let members = filled.pixels().filter(|&&pixel| pixel == params.interior).count();
+        assert!(lines > 0 && lines < members);
+    }
+
+    #[test]
+    fn pixel_and_complex_coordinates_round_trip() {
+        let viewport = (-2.0, 0.5, -1.25, 1.25);
+        let grid = complex_matrix(-2.0, 0.5, -1.25, 1.25, 20).unwrap();
+        let (height, width) = grid.dim();
+        for (py, px) in [(0, 0), (3, 17), (height - 1, width - 1), (height / 2, 0)] {
+            let c = pixel_to_complex(px as f64, py as f64, viewport, width, height);
+            assert_eq!(c, grid[[height - 1 - py, px]]);
+            let (x, y) = complex_to_pixel(c, viewport, width, height);
+            assert_eq!((x.round() as usize, y.round() as usize), (px, py));
+        }
+        assert_eq!(pixel_to_complex(0.0, 0.0, viewport, width, height), Complex::new(-2.0, 1.25));
+    }
+
+    #[test]
+    fn estimated_area_approaches_the_known_value() {
+        let grid = membership_grid(&complex_matrix(-2.0, 0.5, -1.5, 1.5, 200).unwrap(), 200,
+            Fractal::Mandelbrot);
+        let area = estimate_area(&grid, MANDELBROT_BOUNDS);
+        assert!((1.49..1.56).contains(&area), "{area}");
+        assert_eq!(estimate_area(&Array2::from_elem((0, 0), true), MANDELBROT_BOUNDS), 0.0);
+    }
+
+    #[test]
+    fn palettes_parse_from_text_and_gimp_gradients() {
+        let text = "# black to red\n0.0 0 0 0\n\n1.0 255 0 0\n";
+        let palette = Palette::parse(text).unwrap();
+        assert_eq!(palette, Palette::new(vec![(0.0, Rgb([0, 0, 0])), (1.0, Rgb([255, 0, 0]))]));
+
+        let ggr = "GIMP Gradient\nName: Test\n1\n0 0.5 1 0 0 0 1 1 1 1 1 0 0\n";
+        let gradient = Palette::parse(ggr).unwrap();
+        assert_eq!(gradient.color(0.0), Rgb([0, 0, 0]));
+        assert_eq!(gradient.color(0.5), Rgb([128, 128, 128]));
+        assert_eq!(gradient.color(1.0), Rgb([255, 255, 255]));
+
+        let malformed =
+            ["0.5 0 0 0\n0.2 0 0 0", "0 0 0", "1.5 0 0 0", "0 256 0 0", "", "GIMP Gradient\n2\n"];
+        for bad in malformed {
+            let result = Palette::parse(bad);
+            assert!(matches!(result, Err(MandelbrotError::InvalidPalette(_))), "{bad:?}");
+        }
+
+        #[cfg(feature = "fs")]
+        {
+            let path = std::env::temp_dir().join("mandelbrot-palette.ggr");
+            std::fs::write(&path, ggr).unwrap();
+            let loaded = Palette::from_file(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap(), gradient);
+        }
+    }
+
+    #[test]
+    fn z0_seeds_every_orbit_and_defaults_to_zero() {
+        let params = RenderParams {
+            pixel_density: 30,
+            iterations: IterationPolicy::Fixed(50),
+            ..RenderParams::default()
+        };
+        let seeded = |z0| render(&RenderParams { z0, ..params.clone() }).unwrap();
+        assert_eq!(seeded(Complex::new(0.0, 0.0)), render(&params).unwrap());
+        assert_ne!(seeded(Complex::new(0.3, 0.2)), render(&params).unwrap());
+
+        let z0 = Complex::new(0.3, 0.2);
+        let config = EscapeConfig::new(50).with_z0(z0);
+        for c in [Complex::new(-0.2, 0.1), Complex::new(0.3, 0.5), Complex::new(-1.0, 0.0)] {
+            let by_hand = mandelbrot(c)
+                .z(z0)
+                .limit(51)
+                .build()
+                .skip(1)
+                .position(|z| z.norm_sqr() > DEFAULT_BAILOUT);
+            assert_eq!(Fractal::Mandelbrot.escape_time(&c, &config), by_hand);
+            assert_eq!(escape_time(&c, &config), by_hand);
+        }
+    }
+
+    // The reference is `testdata/golden-64x64.png`; when a change to the
+    // output is intended, regenerate it by saving `render` of these params.
+    #[test]
+    fn small_render_matches_the_golden_image() {
+        const TOLERANCE: u8 = 2;
+        let params = RenderParams {
+            x_min: -2.0,
+            x_max: 0.5,
+            y_min: -1.25,
+            y_max: 1.25,
+            size: Some((64, 64)),
+            iterations: IterationPolicy::Fixed(50),
+            color_mode: ColorMode::Smooth,
+            palette: Palette::fire(),
+            ..RenderParams::default()
+        };
+        let golden = image::load_from_memory(include_bytes!("../testdata/golden-64x64.png"))
+            .unwrap()
+            .to_rgb8();
+        let image = render(&params).unwrap();
+        assert_eq!(image.dimensions(), golden.dimensions());
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected = golden[(x, y)];
+            let off = pixel.0.iter().zip(expected.0).any(|(a, b)| a.abs_diff(b) > TOLERANCE);
+            assert!(!off, "pixel ({x}, {y}) is {pixel:?}, expected {expected:?}");
+        }
+    }
+
+    #[test]
+    fn dither_moves_each_channel_by_at_most_one_level() {
+        let params = RenderParams {
+            pixel_density: 40,
+            iterations: IterationPolicy::Fixed(50),
+            color_mode: ColorMode::Smooth,
+            palette: Palette::grayscale(),
+            seed: Some(7),
+            ..RenderParams::default()
+        };
+        let plain = render(&params).unwrap();
+        let dithered = |dither| render(&RenderParams { dither, ..params.clone() }).unwrap();
+        assert_eq!(dithered(Dither::Off), plain);
+        for dither in [Dither::Ordered, Dither::Random] {
+            let image = dithered(dither);
+            assert_ne!(image, plain);
+            for (a, b) in image.as_raw().iter().zip(plain.as_raw()) {
+                assert!(a.abs_diff(*b) <= 1);
+            }
+        }
+        assert_eq!(dithered(Dither::Random), dithered(Dither::Random));
+    }
+
+    #[test]
+    fn render_to_writer_streams_each_format_and_surfaces_write_failures() {
+        struct Broken;
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("closed"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let params = RenderParams { size: Some((12, 8)), ..RenderParams::default() };
+        let mut png = Vec::new();
+        render_to_writer(&params, &mut png, OutputFormat::Png).unwrap();
+        assert_eq!(png, render_png_bytes(&params).unwrap());
+
+        let image = render(&params).unwrap();
+        let mut ppm = Vec::new();
+        render_to_writer(&params, &mut ppm, OutputFormat::Ppm).unwrap();
+        let mut expected = Vec::new();
+        encode_ppm(&image, 12, 8, &mut expected).unwrap();
+        assert_eq!(ppm, expected);
+        let err = encode_ppm(&image, 12, 9, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let formats = [OutputFormat::Png, OutputFormat::Jpeg, OutputFormat::Bmp, OutputFormat::Ppm];
+        for format in formats {
+            let result = render_to_writer(&params, Broken, format);
+            assert!(matches!(result, Err(MandelbrotError::Io(_))), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn smoothing_bailout_takes_the_count_as_a_large_bailout_would() {
+        let points = complex_matrix(-2.0, 0.5, -1.25, 1.25, 12).unwrap();
+        let large = EscapeConfig::new(200).with_bailout_sqr(1e12);
+        let smoothed = EscapeConfig::new(200).with_smoothing_bailout_sqr(1e12);
+        for c in points.iter() {
+            let expected = smooth_escape_time(c, &large);
+            // Members, and orbits too slow to reach the larger radius in time.
+            if is_stable(c, 200) || expected == 200.0 {
+                continue;
+            }
+            let smooth = smooth_escape_time(c, &smoothed);
+            assert!((smooth - expected).abs() < 1e-9, "at {c}: {smooth} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn shading_callback_can_reproduce_the_builtin_linear_coloring() {
+        let params = RenderParams {
+            pixel_density: 30,
+            iterations: IterationPolicy::Fixed(40),
+            palette: Palette::fire(),
+            interior: Rgb([0, 0, 80]),
+            ..RenderParams::default()
+        };
+        let mut calls = 0;
+        let image = render_with_shading(&params, |fate, _| {
+            calls += 1;
+            match fate {
+                Classification::Escaped(n) => params.palette.color(n as f64 / 40.0),
+                _ => params.interior,
+            }
+        })
+        .unwrap();
+        assert_eq!(calls, image.width() * image.height());
+        assert_eq!(image, render(&params).unwrap());
+    }
+
+    #[test]
+    fn log_polar_rings_run_from_the_outer_zoom_at_the_top_to_the_deepest() {
+        let params = RenderParams {
+            iterations: IterationPolicy::Fixed(50),
+            interior: Rgb([0, 0, 255]),
+            ..RenderParams::default()
+        };
+        // Radius 3 at zoom 0.5 lies wholly outside the set, and radius 0.015
+        // at zoom 100 wholly inside the cardioid around 0.
+        let image = render_log_polar(&params, Complex::new(0.0, 0.0), 0.5, 100.0, 24, 16).unwrap();
+        assert_eq!(image.dimensions(), (24, 16));
+        for x in 0..24 {
+            assert_ne!(*image.get_pixel(x, 0), params.interior);
+            assert_eq!(*image.get_pixel(x, 15), params.interior);
+        }
+        assert!(matches!(
+            render_log_polar(&params, Complex::new(0.0, 0.0), 4.0, 2.0, 24, 16),
+            Err(MandelbrotError::InvalidZoomRange { .. })
+        ));
+    }
+
+    #[test]
+    fn suggested_iterations_grow_with_zoom_up_to_the_cap() {
+        let at = |zoom| suggest_max_iter(&Viewport::new(Complex::new(-0.75, 0.1), zoom, 64, 64));
+        assert_eq!(at(1.0), SUGGESTED_BASE_ITER);
+        assert_eq!(at(1024.0), 11 * SUGGESTED_BASE_ITER);
+        assert!(at(1e6) < at(1e12));
+        assert_eq!(at(1e300), MAX_SUGGESTED_ITER);
+        let params = RenderParams {
+            iterations: IterationPolicy::Suggested,
+            ..RenderParams::default()
+        }
+        .with_viewport(&Viewport::new(Complex::new(-0.75, 0.1), 1024.0, 64, 64));
+        assert_eq!(params.max_iter(), at(1024.0));
+    }
+
+    #[test]
+    fn markers_land_on_the_pixel_of_their_point() {
+        let params = RenderParams { size: Some((41, 41)), ..RenderParams::default() };
+        let mut image = RgbImage::new(41, 41);
+        let color = Rgb([255, 0, 0]);
+        let point = pixel_to_complex(10.0, 30.0, MANDELBROT_BOUNDS, 41, 41);
+        let crosshair = Marker { color, size: 3, ..Marker::new(point) };
+        let circle = Marker {
+            shape: MarkerShape::Circle,
+            point: pixel_to_complex(30.0, 10.0, MANDELBROT_BOUNDS, 41, 41),
+            ..crosshair
+        };
+        let outside = Marker { point: Complex::new(10.0, 10.0), ..circle };
+        draw_markers(&mut image, &params, &[crosshair, circle, outside]);
+        for (x, y) in [(10, 30), (7, 30), (13, 30), (10, 27), (10, 33)] {
+            assert_eq!(*image.get_pixel(x, y), color, "({x}, {y})");
+        }
+        for (x, y) in [(33, 10), (27, 10), (30, 7), (30, 13)] {
+            assert_eq!(*image.get_pixel(x, y), color, "({x}, {y})");
+        }
+        assert_ne!(*image.get_pixel(30, 10), color);
+        let crossed = image.enumerate_pixels().filter(|&(_, y, p)| y >= 20 && *p == color);
+        assert_eq!(crossed.count(), 13);
+
+        // On a rotated render markers follow their point round.
+        let view = Viewport::new(Complex::new(-0.75, 0.0), 1.5, 41, 41).with_rotation(0.7);
+        let params = RenderParams::default().with_viewport(&view);
+        let mut image = RgbImage::new(41, 41);
+        let point = view.pixel_to_complex(8.0, 12.0);
+        draw_markers(&mut image, &params, &[Marker { color, size: 0, ..Marker::new(point) }]);
+        let marked = image.enumerate_pixels().filter(|&(_, _, p)| *p == color);
+        assert_eq!(marked.map(|(x, y, _)| (x, y)).collect::<Vec<_>>(), [(8, 12)]);
+    }
+
+    #[test]
+    fn params_display_parses_back_to_the_same_render() {
+        let params = RenderParams {
+            x_min: -0.7453,
+            x_max: -0.7447,
+            y_min: 0.1126,
+            y_max: 0.1132,
+            size: Some((48, 48)),
+            iterations: IterationPolicy::AutoZoom { base: 100 },
+            bailout: 16.0,
+            ..RenderParams::default()
+        };
+        let text = params.to_string();
+        assert!(text.starts_with("x_min=-0.7453 x_max=-0.7447 "), "{text}");
+        let parsed: RenderParams = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(render(&parsed).unwrap(), render(&params).unwrap());
+
+        for bad in ["x_min", "x_min=left", "hue=3", "power=2 power=3", "size=48"] {
+            let err = bad.parse::<RenderParams>().unwrap_err();
+            assert!(matches!(err, MandelbrotError::InvalidParams(_)), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    fn params_display_keeps_the_fractal_and_coloring() {
+        let params = RenderParams {
+            size: Some((40, 30)),
+            iterations: IterationPolicy::Fixed(80),
+            fractal: Fractal::Julia(Complex::new(-0.8, 0.156)),
+            z0: Complex::new(0.01, -0.02),
+            color_mode: ColorMode::Composite {
+                trap: Trap::Line { through: Complex::new(0.1, 0.2), angle: 0.5 },
+            },
+            color_scale: ColorScale::Log,
+            palette_offset: 0.25,
+            interior: Rgb([10, 20, 30]),
+            interior_mode: InteriorMode::Period,
+            smoothing_bailout: Some(256.0),
+            gamma: 2.2,
+            dither: Dither::Random,
+            precision: Precision::Double,
+            samples_per_pixel: 2,
+            jitter: true,
+            seed: Some(7),
+            ..RenderParams::default()
+        };
+        let text = params.to_string();
+        assert!(text.contains(" fractal=julia:-0.8,0.156 "), "{text}");
+        assert!(text.contains(" color_mode=composite:line:0.1,0.2,0.5 "), "{text}");
+        let parsed: RenderParams = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.fractal, params.fractal);
+        assert_eq!(parsed.color_mode, params.color_mode);
+        assert_eq!(render(&parsed).unwrap(), render(&params).unwrap());
+
+        for bad in ["fractal=julia", "color_mode=orbit-trap:ring:0,0", "interior=1,2"] {
+            assert!(bad.parse::<RenderParams>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn params_display_keeps_the_palettes() {
+        let params = RenderParams {
+            size: Some((40, 30)),
+            iterations: IterationPolicy::Fixed(60),
+            palette: Palette::fire(),
+            interior_mode: InteriorMode::Magnitude,
+            interior_palette: Palette::blue_white().with_space(ColorSpace::Hsl),
+            ..RenderParams::default()
+        };
+        let text = params.to_string();
+        assert!(text.contains(" palette=0:0,0,0/0.3:128,0,0/"), "{text}");
+        let parsed: RenderParams = text.parse().unwrap();
+        assert_eq!(parsed.palette, params.palette);
+        assert_eq!(parsed.interior_palette, params.interior_palette);
+        assert_eq!(render(&parsed).unwrap(), render(&params).unwrap());
+
+        for bad in ["palette=0:0,0,0", "palette=0:0,0,0@cmy", "palette=2:0,0,0@rgb", "palette=@"] {
+            let err = bad.parse::<RenderParams>().unwrap_err();
+            assert!(matches!(err, MandelbrotError::InvalidParams(_)), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn cached_zoom_frames_reuse_escapes_without_changing_pixels() {
+        let params = RenderParams {
+            size: Some((32, 24)),
+            iterations: IterationPolicy::Fixed(60),
+            palette: Palette::fire(),
+            ..RenderParams::default()
+        };
+        let target = Complex::new(-0.75, 0.1);
+        let dir = std::env::temp_dir().join("mandelbrot-cached-zoom");
+        let stats = render_zoom_sequence_cached(&params, target, 1.0, 1.02, 4, &dir).unwrap();
+        assert_eq!(stats.lookups, 4 * 32 * 24);
+        assert!(stats.hits > stats.lookups / 10, "{stats:?}");
+
+        let single = std::env::temp_dir().join("mandelbrot-cached-zoom-single");
+        for frame in 0..4 {
+            let zoom = 1.02f64.powf(frame as f64 / 3.0);
+            let alone = render_zoom_sequence_cached(&params, target, zoom, zoom, 1, &single);
+            assert_eq!(alone.unwrap().hits, 0);
+            let open = |dir: &std::path::Path, frame| {
+                image::open(dir.join(format!("frame_{frame:04}.png"))).unwrap().to_rgb8()
+            };
+            assert_eq!(open(&dir, frame), open(&single, 0), "frame {frame}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&single).unwrap();
+
+        // Past `f64` depth the frames are `render`'s own, at `params.precision`.
+        let deep = RenderParams { size: Some((8, 6)), ..params };
+        let (zoom, cached, plain) = (1e13, dir.join("cached"), dir.join("plain"));
+        let view = Viewport::new(target, zoom, 8, 6);
+        let path = render_path(&deep.clone().with_viewport(&view));
+        assert!(matches!(path, RenderPath::Arbitrary { .. }), "{path:?}");
+        let stats = render_zoom_sequence_cached(&deep, target, zoom, zoom * 1.5, 2, &cached);
+        assert_eq!(stats.unwrap(), ZoomCacheStats::default());
+        render_zoom_sequence(&deep, target, zoom, zoom * 1.5, 2, &plain).unwrap();
+        for frame in ["frame_0000.png", "frame_0001.png"] {
+            let open = |dir: &std::path::Path| image::open(dir.join(frame)).unwrap().to_rgb8();
+            assert_eq!(open(&cached), open(&plain), "{frame}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn interesting_points_keep_a_dive_on_the_boundary() {
+        let mut viewport = Viewport::new(Complex::new(-0.75, 0.0), 1.0, 48, 36);
+        for _ in 0..4 {
+            let point = find_interesting_point(&viewport, 200);
+            let (x_min, x_max, y_min, y_max) = viewport.bounds();
+            assert!((x_min..=x_max).contains(&point.re) && (y_min..=y_max).contains(&point.im));
+            assert!(!is_stable(&point, 200), "{point}");
+            viewport = Viewport::new(point, viewport.zoom * 4.0, 48, 36);
+            let (x_min, x_max, y_min, y_max) = viewport.bounds();
+            let grid = complex_matrix(x_min, x_max, y_min, y_max, (48.0 / (x_max - x_min)) as i128);
+            let members = membership_grid(&grid.unwrap(), 200, Fractal::Mandelbrot);
+            assert!(members.iter().any(|&m| m) && members.iter().any(|&m| !m));
+        }
+
+        // A rotated view picks one of its own, rotated, pixels.
+        let turned = Viewport::new(Complex::new(-0.75, 0.1), 2.0, 48, 36).with_rotation(1.0);
+        let point = find_interesting_point(&turned, 200);
+        let (px, py) = turned.complex_to_pixel(point);
+        assert!((px - px.round()).abs() < 1e-6 && (py - py.round()).abs() < 1e-6, "{px} {py}");
+        assert!((0.0..=47.0).contains(&px.round()) && (0.0..=35.0).contains(&py.round()));
+        assert!(!is_stable(&point, 200), "{point}");
+    }
+
+    #[test]
+    fn rotation_turns_sampling_about_the_center() {
+        let view = Viewport::new(Complex::new(-0.75, 0.1), 1.5, 30, 20);
+        let turned = view.with_rotation(0.5);
+        let (px, py) = turned.complex_to_pixel(Complex::new(-0.5, 0.3));
+        let back = turned.pixel_to_complex(px, py);
+        assert!((back - Complex::new(-0.5, 0.3)).norm() < 1e-12);
+        let center = turned.pixel_to_complex(14.5, 9.5);
+        assert!((center - view.center).norm() < 1e-12);
+
+        let params = RenderParams {
+            iterations: IterationPolicy::Fixed(40),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        }
+        .with_viewport(&view);
+        let upright = render(&params).unwrap();
+        let unturned = params.clone().with_viewport(&view.with_rotation(0.0));
+        assert_eq!(render(&unturned).unwrap(), upright);
+
+        let rotated = params.with_viewport(&turned);
+        assert_ne!(render(&rotated).unwrap(), upright);
+        let mut points = Vec::new();
+        render_with_shading(&rotated, |_, point| {
+            points.push(*point);
+            Rgb([0, 0, 0])
+        })
+        .unwrap();
+        for (k, point) in points.into_iter().enumerate() {
+            let (x, y) = ((k % 30) as f64, (k / 30) as f64);
+            let expected = turned.pixel_to_complex(x, y);
+            assert!((point - expected).norm() < 1e-12, "{point} != {expected}");
+        }
+    }
+
+    #[test]
+    fn mipmaps_box_filter_the_full_render() {
+        let params = RenderParams {
+            size: Some((5, 4)),
+            iterations: IterationPolicy::Fixed(30),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let images = render_with_mipmaps(&params, 3).unwrap();
+        let sizes = images.iter().map(|image| image.dimensions()).collect::<Vec<_>>();
+        assert_eq!(sizes, [(5, 4), (3, 2), (2, 1), (1, 1)]);
+        assert_eq!(images[0], render(&params).unwrap());
+        let full = &images[0];
+        let average = |pixels: &[(u32, u32)]| {
+            let total: u32 = pixels.iter().map(|&(x, y)| full.get_pixel(x, y).0[0] as u32).sum();
+            ((total + pixels.len() as u32 / 2) / pixels.len() as u32) as u8
+        };
+        assert_eq!(images[1].get_pixel(0, 0).0[0], average(&[(0, 0), (1, 0), (0, 1), (1, 1)]));
+        assert_eq!(images[1].get_pixel(2, 1).0[0], average(&[(4, 2), (4, 3)]));
+    }
+
+    #[test]
+    fn axis_coordinates_match_complex_matrix() {
+        let bounds = (-2.0, 0.5, -1.1, 0.9);
+        let matrix = complex_matrix(bounds.0, bounds.1, bounds.2, bounds.3, 7).unwrap();
+        let (height, width) = matrix.dim();
+        let (re, im) = axis_coordinates(bounds, width, height);
+        assert_eq!((re.len(), im.len()), (width, height));
+        for ((i, j), point) in matrix.indexed_iter() {
+            assert_eq!(*point, Complex::new(re[j], im[i]));
+            let pixel = pixel_to_complex(j as f64, (height - 1 - i) as f64, bounds, width, height);
+            assert!((pixel - *point).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn interior_budget_only_loses_late_escapes() {
+        let params = RenderParams {
+            size: Some((40, 30)),
+            iterations: IterationPolicy::Fixed(200),
+            palette: Palette::grayscale(),
+            interior: Rgb([255, 0, 0]),
+            ..RenderParams::default()
+        };
+        let full = render(&params).unwrap();
+        let budgeted = render(&RenderParams { interior_budget: Some(30), ..params.clone() });
+        let budgeted = budgeted.unwrap();
+        assert_ne!(budgeted, full);
+        let (width, height) = params.grid_size();
+        let re = params.sampling.axis(params.x_min, params.x_max, width);
+        let im = params.sampling.axis(params.y_min, params.y_max, height);
+        for (x, y, pixel) in budgeted.enumerate_pixels() {
+            let point = Complex::new(re[x as usize], im[height - 1 - y as usize]);
+            match escape_time(&point, &params.escape_config()) {
+                Some(n) if n < 30 => assert_eq!(pixel, full.get_pixel(x, y), "at {point}"),
+                _ => assert_eq!(*pixel, params.interior, "at {point}"),
+            }
+        }
+        let none = RenderParams { interior_budget: Some(0), ..params };
+        assert!(matches!(render(&none), Err(MandelbrotError::InvalidIterations(0))));
+    }
+
+    #[test]
+    fn keyframes_hold_and_ease_between_views() {
+        let start = Viewport::new(Complex::new(-0.5, 0.0), 1.0, 16, 12);
+        let end = Viewport::new(Complex::new(-0.75, 0.1), 64.0, 16, 12).with_rotation(1.0);
+        let keyframes = [
+            Keyframe { viewport: start, hold_frames: 2 },
+            Keyframe { viewport: end, hold_frames: 1 },
+        ];
+        let linear = keyframe_viewports(&keyframes, 3, Easing::Linear);
+        assert_eq!(linear.len(), 3 + 3 + 2);
+        assert_eq!(linear[..3], [start; 3]);
+        assert_eq!(linear[6..], [end; 2]);
+        let zooms = linear.iter().map(|view| view.zoom).collect::<Vec<_>>();
+        for k in 3..6 {
+            assert!((zooms[k] / zooms[k - 1] - 64f64.powf(0.25)).abs() < 1e-9);
+        }
+        assert!((linear[4].rotation - 0.5).abs() < 1e-12);
+        // The first step covers the most ground, while the view is widest.
+        let step = |k: usize| (linear[k].center - linear[k - 1].center).norm();
+        assert!(step(3) > step(4) && step(4) > step(5) && step(5) > step(6));
+
+        let eased = keyframe_viewports(&keyframes, 3, Easing::EaseInOut);
+        assert!(eased[3].zoom < linear[3].zoom && eased[5].zoom > linear[5].zoom);
+        assert!((eased[4].zoom - linear[4].zoom).abs() < 1e-9);
+        assert!(keyframe_viewports(&[], 3, Easing::Linear).is_empty());
+    }
+
+    #[test]
+    fn render_picks_its_path_and_reports_it() {
+        let params = RenderParams {
+            size: Some((40, 30)),
+            iterations: IterationPolicy::Fixed(60),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let fast = if cfg!(feature = "simd") { RenderPath::Simd } else { RenderPath::Scalar };
+        assert_eq!(render_path(&params), fast);
+        let (image, stats) = render_with_stats(&params).unwrap();
+        assert_eq!(stats.path, fast);
+        let linear = render_with_shading(&params, |fate, _| match fate {
+            Classification::Escaped(n) => params.palette.color(n as f64 / 60.0),
+            _ => params.interior,
+        });
+        assert_eq!(image, linear.unwrap());
+
+        let julia = RenderParams { fractal: Fractal::Julia(Complex::new(-0.8, 0.156)), ..params };
+        assert_eq!(render_path(&julia), RenderPath::Scalar);
+        let deep = RenderParams {
+            x_min: -0.75 - 1e-14,
+            x_max: -0.75 + 1e-14,
+            y_min: 0.1 - 1e-14,
+            y_max: 0.1 + 1e-14,
+            size: Some((8, 8)),
+            ..RenderParams::default()
+        };
+        assert!(matches!(render_path(&deep), RenderPath::Arbitrary { .. }));
+        let perturbed = RenderParams { precision: Precision::Perturbation, ..deep };
+        assert!(matches!(render_path(&perturbed), RenderPath::Perturbation { .. }));
+
+        #[cfg(feature = "simd")]
+        {
+            let config = EscapeConfig::new(80);
+            let points = complex_matrix(-2.0, 0.5, -1.2, 1.2, 12).unwrap();
+            let points = points.iter().copied().collect::<Vec<_>>();
+            let escapes = batch::simd_escapes(&points, &config, Lanes::Four);
+            for (point, escape) in points.iter().zip(escapes) {
+                assert_eq!(escape, Fractal::Mandelbrot.escape(point, &config), "at {point}");
+            }
+        }
+    }
+
+    #[test]
+    fn svg_traces_the_boundary_as_closed_loops() {
+        let grid = Array2::from_shape_fn((3, 3), |(i, j)| (i, j) == (1, 1));
+        let diamond = [(1.0, 0.5), (1.5, 1.0), (1.0, 1.5), (0.5, 1.0)];
+        let loops = trace_boundary(&grid);
+        assert_eq!(loops.len(), 1);
+        let (vertices, last) = loops[0].split_at(4);
+        assert_eq!(last, [vertices[0]]);
+        assert!(diamond.iter().all(|vertex| vertices.contains(vertex)));
+        // Members touching only at a corner stay apart.
+        let saddle = Array2::from_shape_fn((2, 2), |(i, j)| i == j);
+        assert_eq!(trace_boundary(&saddle).len(), 2);
+        let full = Array2::from_elem((4, 3), true);
+        assert_eq!(trace_boundary(&full)[0].len(), 9);
+
+        let params = RenderParams {
+            size: Some((60, 40)),
+            iterations: IterationPolicy::Fixed(40),
+            interior: Rgb([255, 0, 0]),
+            ..RenderParams::default()
+        };
+        let svg = render_svg(&params).unwrap();
+        assert!(svg.starts_with("<svg ") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(r#"width="60" height="40""#));
+        assert!(svg.contains(r##"fill="none" stroke="#ff0000""##));
+        let paths = svg.matches('M').count();
+        assert!(paths >= 1 && paths == svg.matches('Z').count());
+    }
+
+    #[test]
+    fn per_pixel_iteration_limits_cap_each_point() {
+        let params = RenderParams {
+            size: Some((30, 20)),
+            iterations: IterationPolicy::Fixed(80),
+            palette: Palette::grayscale(),
+            ..RenderParams::default()
+        };
+        let full = render(&params).unwrap();
+        let uniform = Array2::from_elem((20, 30), 80);
+        assert_eq!(render_with_iteration_limits(&params, &uniform).unwrap(), full);
+
+        // The left half only gets 5 iterations.
+        let limits = Array2::from_shape_fn((20, 30), |(_, j)| if j < 15 { 5 } else { 80 });
+        let capped = render_with_iteration_limits(&params, &limits).unwrap();
+        let bounds = (params.x_min, params.x_max, params.y_min, params.y_max);
+        let (re, im) = axis_coordinates(bounds, 30, 20);
+        let points = Array2::from_shape_fn((20, 30), |(i, j)| Complex::new(re[j], im[i]));
+        let escapes = escape_grid_with_limits(&points, &limits, Fractal::Mandelbrot).unwrap();
+        for (x, y, pixel) in capped.enumerate_pixels() {
+            let (i, j) = (19 - y as usize, x as usize);
+            match escapes[[i, j]] {
+                Some(n) => {
+                    assert!(n < limits[[i, j]]);
+                    assert_eq!(pixel, full.get_pixel(x, y));
+                }
+                None => assert_eq!(*pixel, params.interior),
+            }
+        }
+        assert!(escapes.slice(ndarray::s![.., ..15]).iter().any(Option::is_none));
+
+        let wrong = Array2::from_elem((30, 20), 80);
+        assert!(matches!(
+            render_with_iteration_limits(&params, &wrong),
+            Err(MandelbrotError::SizeMismatch { expected: (30, 20), found: (20, 30) })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn saving_creates_parent_directories_and_names_unwritable_paths() {
+        let dir = std::env::temp_dir().join("mandelbrot-nested-output");
+        let _ = std::fs::remove_dir_all(&dir);
+        let image = RgbImage::new(4, 3);
+        let params = RenderParams::default();
+        let nested = dir.join("a").join("b").join("out.png");
+        save(&image, &nested).unwrap();
+        assert_eq!(image::open(&nested).unwrap().height(), 3);
+        // `nested` is a file, so nothing can be written beneath it.
+        let blocked = nested.join("out.png");
+        let err = save(&image, &blocked).unwrap_err();
+        assert!(matches!(err, MandelbrotError::Io(_)));
+        assert!(err.to_string().contains(&nested.display().to_string()), "{err}");
+        // An image that fails to encode leaves no empty file behind.
+        let empty = dir.join("empty.png");
+        let result = save_with_params(&RgbImage::new(0, 0), &params, &empty, OutputFormat::Png);
+        assert!(result.is_err());
+        assert!(!empty.exists());
+        // Writes that fail after the file is open, as on a full disk, name it too.
+        #[cfg(target_os = "linux")]
+        {
+            let full = std::path::Path::new("/dev/full");
+            let formats = [
+                OutputFormat::Png,
+                OutputFormat::Jpeg,
+                OutputFormat::Bmp,
+                OutputFormat::Ppm,
+            ];
+            for format in formats {
+                let err = save_as(&image, full, format).unwrap_err();
+                assert!(err.to_string().contains("/dev/full"), "{format:?}: {err}");
+            }
+        }
+
+        #[cfg(feature = "chart")]
+        {
+            let style = PlotStyle::default();
+            let plot = dir.join("c").join("plot.png");
+            plot_mandelbrot(vec![(0.0, 0.0)], -1.0..1.0, -1.0..1.0, 10, 60, &style, &plot).unwrap();
+            assert!(plot.exists());
+            let err = plot_mandelbrot(vec![], -1.0..1.0, -1.0..1.0, 10, 60, &style, &blocked);
+            let err = err.unwrap_err();
+            assert!(err.to_string().contains(&nested.display().to_string()), "{err}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pixel_stages_run_in_order_with_the_sampled_point() {
+        let params = RenderParams {
+            pixel_density: 20,
+            ..RenderParams::default()
+        };
+        let gamma: Vec<Box<dyn PixelStage>> = vec![Box::new(Gamma::new(2.2).unwrap())];
+        let gamma_params = RenderParams {
+            gamma: 2.2,
+            ..params.clone()
+        };
+        assert_eq!(
+            render_with_stages(&params, &gamma).unwrap(),
+            render(&gamma_params).unwrap()
+        );
+
+        let tint = Tint {
+            color: Rgb([255, 0, 0]),
+            amount: 0.5,
+        };
+        let dither = OrderedDither::new(2).unwrap();
+        let tint_first: Vec<Box<dyn PixelStage>> = vec![Box::new(tint), Box::new(dither)];
+        let dither_first: Vec<Box<dyn PixelStage>> = vec![Box::new(dither), Box::new(tint)];
+        let a = render_with_stages(&params, &tint_first).unwrap();
+        let b = render_with_stages(&params, &dither_first).unwrap();
+        assert_ne!(a, b);
+        assert!(a.pixels().flat_map(|p| p.0).all(|c| c == 0 || c == 255));
+
+        struct Probe(Vec<Complex<f64>>, usize);
+        impl PixelStage for Probe {
+            fn apply(&self, color: Rgb<u8>, ctx: &PixelContext) -> Rgb<u8> {
+                assert!(ctx.x < ctx.width && ctx.y < ctx.height);
+                let i = (ctx.height - 1 - ctx.y) as usize * ctx.width as usize + ctx.x as usize;
+                assert_eq!(ctx.point, self.0[i]);
+                assert_eq!(self.1, ctx.width as usize * ctx.height as usize);
+                color
+            }
+        }
+        let plain = render(&params).unwrap();
+        let (width, height) = (plain.width() as usize, plain.height() as usize);
+        let (re, im) = axis_coordinates(
+            (params.x_min, params.x_max, params.y_min, params.y_max),
+            width,
+            height,
+        );
+        let points = im
+            .iter()
+            .flat_map(|&y| re.iter().map(move |&x| Complex::new(x, y)))
+            .collect::<Vec<_>>();
+        let probe: Vec<Box<dyn PixelStage>> = vec![Box::new(Probe(points, width * height))];
+        assert_eq!(render_with_stages(&params, &probe).unwrap(), plain);
+
+        for gamma in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(Gamma::new(gamma), Err(MandelbrotError::InvalidGamma(_))));
+        }
+        for levels in [0, 1] {
+            let err = OrderedDither::new(levels).unwrap_err();
+            assert!(matches!(err, MandelbrotError::InvalidParams(_)), "{err}");
+        }
+    }
+
+    #[test]
+    fn optimized_membership_matches_brute_force() {
+        let views = [
+            Viewport::new(Complex::new(-0.75, 0.0), 1.0, 120, 90),
+            Viewport::new(Complex::new(-0.1, 0.8), 8.0, 80, 60),
+            Viewport::new(Complex::new(-0.75, 0.1), 4.0, 64, 64).with_rotation(0.5),
+        ];
+        for viewport in &views {
+            verify_grid(viewport, 200).unwrap();
+        }
+    }
+
+    #[test]
+    fn sampling_offset_shifts_by_fractions_of_a_pixel() {
+        let plain = complex_matrix(-2.0, 0.5, -1.0, 1.0, 20).unwrap();
+        let unshifted = complex_matrix_with_offset(-2.0, 0.5, -1.0, 1.0, 20, (0.0, 0.0)).unwrap();
+        assert_eq!(plain, unshifted);
+
+        let shifted = complex_matrix_with_offset(-2.0, 0.5, -1.0, 1.0, 20, (0.5, -0.25)).unwrap();
+        assert_eq!(shifted.dim(), plain.dim());
+        let (rows, cols) = plain.dim();
+        let dx = 2.5 / (cols - 1) as f64;
+        let dy = 2.0 / (rows - 1) as f64;
+        for (a, b) in plain.iter().zip(&shifted) {
+            assert!((b.re - a.re - 0.5 * dx).abs() < 1e-12);
+            assert!((b.im - a.im + 0.25 * dy).abs() < 1e-12);
+        }
+        // A whole-pixel shift lands on the next column of the plain grid.
+        let whole = complex_matrix_with_offset(-2.0, 0.5, -1.0, 1.0, 20, (1.0, 0.0)).unwrap();
+        assert!((whole[[3, 4]] - plain[[3, 5]]).norm() < 1e-12);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn render_params_round_trip_through_json() {
+        let params = RenderParams {
+            x_min: -0.743_643_887_037_151,
+            x_max: -0.743_643_887_037_15,
+            y_min: 0.131_825_904_205_33,
+            y_max: 0.131_825_904_205_331,
+            pixel_density: 170_141_183_460_469_231_731_687_303_715_884,
+            size: Some((48, 32)),
+            iterations: IterationPolicy::AutoZoom { base: 150 },
+            smoothing_bailout: Some(1e10),
+            fractal: Fractal::Julia(Complex::new(-0.8, 0.156)),
+            color_mode: ColorMode::Composite {
+                trap: Trap::Line {
+                    through: Complex::new(0.1, -0.2),
+                    angle: 0.3,
+                },
+            },
+            palette: Palette::new(vec![(0.0, Rgb([1, 2, 3])), (0.4, Rgb([250, 128, 7]))])
+                .with_space(ColorSpace::Lab),
+            interior: Rgb([10, 20, 30]),
+            dither: Dither::Ordered,
+            interior_budget: Some(90),
+            rotation: 0.1,
+            precision: Precision::Arbitrary { bits: 96 },
+            samples_per_pixel: 2,
+            seed: Some(u64::MAX),
+            ..RenderParams::default()
+        };
+        let json = params.to_json().unwrap();
+        let back = RenderParams::from_json(&json).unwrap();
+        assert_eq!(back.to_json().unwrap(), json);
+        assert_eq!(back.to_string(), params.to_string());
+        assert_eq!(back.palette, params.palette);
+        assert_eq!(back.color_mode, params.color_mode);
+        assert_eq!(back.pixel_density, params.pixel_density);
+
+        let viewport = Viewport::new(Complex::new(-0.75, 0.1), 3.5, 64, 48).with_rotation(1.2);
+        let text = serde_json::to_string(&viewport).unwrap();
+        assert_eq!(serde_json::from_str::<Viewport>(&text).unwrap(), viewport);
+
+        // Missing fields keep their defaults.
+        let partial = RenderParams::from_json(r#"{"power": 3, "fractal": "Tricorn"}"#).unwrap();
+        assert_eq!((partial.power, partial.fractal), (3, Fractal::Tricorn));
+        let expected = RenderParams {
+            power: 3,
+            fractal: Fractal::Tricorn,
+            ..RenderParams::default()
+        };
+        assert_eq!(partial.to_string(), expected.to_string());
+        assert!(RenderParams::from_json(r#"{"palette": {"stops": []}}"#).is_err());
+    }
+
+    #[test]
+    fn nebulabrot_channels_are_buddhabrots_of_their_caps() {
+        let params = RenderParams {
+            pixel_density: 20,
+            seed: Some(3),
+            ..RenderParams::default()
+        };
+        let nebula = render_nebulabrot(50_000, [20, 100, 400], &params).unwrap();
+        for (k, max_iter) in [20, 100, 400].into_iter().enumerate() {
+            let gray = render_buddhabrot(50_000, max_iter, &params).unwrap();
+            assert!(nebula.pixels().zip(gray.pixels()).all(|(n, g)| n[k] == g[0]));
+        }
+        assert!(nebula.pixels().any(|p| p[0] != p[2]));
+    }
+
+    #[test]
+    fn resolution_matrix_has_the_requested_shape() {
+        let c = complex_matrix_for_resolution((-2.0, 0.5, -1e-9, 1e-9), 64, 48).unwrap();
+        assert_eq!(c.dim(), (48, 64));
+        assert_eq!(c[[0, 0]], Complex::new(-2.0, -1e-9));
+        assert_eq!(c[[47, 63]], Complex::new(0.5, 1e-9));
+        let (re, im) = axis_coordinates((-2.0, 0.5, -1e-9, 1e-9), 64, 48);
+        assert!(c.indexed_iter().all(|((i, j), z)| *z == Complex::new(re[j], im[i])));
+
+        let matching = complex_matrix_for_resolution((-2.0f32, 0.5, -1.0, 1.0), 50, 40).unwrap();
+        assert_eq!(matching, complex_matrix(-2.0f32, 0.5, -1.0, 1.0, 20).unwrap());
+        assert!(complex_matrix_for_resolution((-2.0, 0.5, -1.0, 1.0), 0, 10).is_err());
+        assert!(complex_matrix_for_resolution((0.5, -2.0, -1.0, 1.0), 10, 10).is_err());
+    }
+
+    #[test]
+    fn layers_blend_in_order_by_opacity() {
+        let mandelbrot = RenderParams {
+            size: Some((40, 30)),
+            ..RenderParams::default()
+        };
+        let julia = RenderParams {
+            fractal: Fractal::Julia(Complex::new(-0.8, 0.156)),
+            palette: Palette::fire(),
+            ..mandelbrot.clone()
+        };
+        let below = render(&mandelbrot).unwrap();
+        let above = render(&julia).unwrap();
+        let stack = |opacity| {
+            let layers = [Layer::new(mandelbrot.clone(), 1.0), Layer::new(julia.clone(), opacity)];
+            render_layers(&layers)
+        };
+        assert_eq!(stack(0.0).unwrap(), below);
+        assert_eq!(stack(1.0).unwrap(), above);
+        let half = stack(0.5).unwrap();
+        for ((h, b), a) in half.pixels().zip(below.pixels()).zip(above.pixels()) {
+            for k in 0..3 {
+                let mean = (b[k] as f64 + a[k] as f64) / 2.0;
+                assert!((h[k] as f64 - mean).abs() <= 0.5);
+            }
+        }
+
+        let smaller = RenderParams {
+            size: Some((20, 30)),
+            ..julia.clone()
+        };
+        let mismatched = render_layers(&[Layer::new(mandelbrot, 1.0), Layer::new(smaller, 0.5)]);
+        assert!(matches!(mismatched, Err(MandelbrotError::SizeMismatch { .. })));
+        assert!(matches!(render_layers(&[]), Err(MandelbrotError::InvalidParams(_))));
+        for opacity in [-0.1, 1.5, f64::NAN] {
+            let layers = [Layer::new(julia.clone(), 1.0), Layer::new(julia.clone(), opacity)];
+            assert!(matches!(render_layers(&layers), Err(MandelbrotError::InvalidParams(_))));
+        }
+    }
+
+    #[test]
+    fn escape_value_grid_matches_the_two_pass_values() {
+        let c = complex_matrix(-2.0, 0.5, -1.0, 1.0, 30).unwrap();
+        let config = EscapeConfig::new(80);
+        let smooth = escape_value_grid(&c, 80, Fractal::Mandelbrot, GridValue::Smooth);
+        let counts = escape_value_grid(&c, 80, Fractal::Mandelbrot, GridValue::Count);
+        let escapes = escape_grid(&c, 80, Fractal::Mandelbrot);
+        assert_eq!(smooth.dim(), c.dim());
+        for (((point, s), n), escape) in c.iter().zip(&smooth).zip(&counts).zip(&escapes) {
+            match escape {
+                Some(k) => {
+                    assert_eq!(*n, *k as f64);
+                    assert_eq!(*s, smooth_escape_time(point, &config));
+                }
+                None => assert!(s.is_infinite() && n.is_infinite()),
+            }
+        }
+        assert_eq!(
+            smooth.map(|s| s.is_infinite()),
+            membership_grid(&c, 80, Fractal::Mandelbrot)
+        );
+    }
+
+    #[test]
+    fn palette_offset_cycles_colors_with_period_one() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(50),
+            color_mode: ColorMode::Smooth,
+            palette: Palette::fire(),
+            ..RenderParams::default()
+        };
+        let with_offset = |palette_offset| {
+            render(&RenderParams {
+                palette_offset,
+                ..params.clone()
+            })
+            .unwrap()
+        };
+        let plain = render(&params).unwrap();
+        let shifted = with_offset(0.25);
+        assert_ne!(shifted, plain);
+        assert_eq!(with_offset(1.25), shifted);
+        assert_eq!(with_offset(-0.75), shifted);
+        assert_eq!(with_offset(2.0), with_offset(1.0));
+
+        // Interior pixels keep their color.
+        for (a, b) in plain.pixels().zip(shifted.pixels()) {
+            if *a == params.interior {
+                assert_eq!(b, a);
+            }
+        }
+        let invalid = RenderParams {
+            palette_offset: f64::NAN,
+            ..params
+        };
+        assert!(render(&invalid).is_err());
+    }
+
+    #[test]
+    fn sample_counts_set_each_axis_independently() {
+        let c = complex_matrix_with_samples(-1.0, 1.0, -1.0, 1.0, 101, 11).unwrap();
+        assert_eq!(c.dim(), (11, 101));
+        assert!((c[[0, 1]].re - c[[0, 0]].re - 0.02).abs() < 1e-12);
+        assert!((c[[1, 0]].im - c[[0, 0]].im - 0.2).abs() < 1e-12);
+        assert_eq!(
+            complex_matrix_with_samples(-2.0, 0.5, -1.0, 1.0, 50, 40).unwrap(),
+            complex_matrix(-2.0, 0.5, -1.0, 1.0, 20).unwrap()
+        );
+        assert!(complex_matrix_with_samples(-1.0, 1.0, -1.0, 1.0, 10, 0).is_err());
+    }
+
+    #[test]
+    fn centroid_of_the_set_lies_on_the_real_axis() {
+        let c = complex_matrix(-2.0, 0.5, -1.5, 1.5, 100).unwrap();
+        let grid = membership_grid(&c, 100, Fractal::Mandelbrot);
+        let centroid = set_centroid(&grid, MANDELBROT_BOUNDS);
+        // The set's centre of mass is near -0.2867.
+        assert!((centroid.re + 0.287).abs() < 0.01, "{centroid}");
+        assert!(centroid.im.abs() < 1e-9, "{centroid}");
+
+        let mut corner = Array2::from_elem((3, 5), false);
+        corner[[0, 4]] = true;
+        assert_eq!(set_centroid(&corner, (0.0, 4.0, 0.0, 2.0)), Complex::new(4.0, 0.0));
+        let empty = Array2::from_elem((3, 5), false);
+        assert_eq!(set_centroid(&empty, (0.0, 4.0, 0.0, 2.0)), Complex::new(2.0, 1.0));
+
+        let params = RenderParams {
+            pixel_density: 100,
+            iterations: IterationPolicy::Fixed(100),
+            ..RenderParams::default()
+        };
+        let rendered = render_centroid(&params).unwrap();
+        assert!((rendered - centroid).norm() < 1e-9, "{rendered} vs {centroid}");
+        // Julia sets are symmetric about 0.
+        let julia = RenderParams {
+            x_min: -1.5,
+            x_max: 1.5,
+            fractal: Fractal::Julia(Complex::new(-0.8, 0.156)),
+            ..params.clone()
+        };
+        let julia = render_centroid(&julia).unwrap();
+        assert!(julia.norm() < 1e-6, "{julia}");
+        let bad = RenderParams { bailout: f64::NAN, ..params };
+        assert!(matches!(render_centroid(&bad), Err(MandelbrotError::InvalidParams(_))));
+        let huge = RenderParams { pixel_density: 1 << 20, ..RenderParams::default() };
+        let err = render_centroid(&huge).unwrap_err();
+        assert!(matches!(err, MandelbrotError::DimensionsTooLarge { .. }), "{err}");
+    }
+
+    #[test]
+    fn progressive_passes_sharpen_into_the_full_render() {
+        let params = RenderParams {
+            pixel_density: 20,
+            iterations: IterationPolicy::Fixed(60),
+            color_mode: ColorMode::Smooth,
+            palette: Palette::fire(),
+            samples_per_pixel: 2,
+            ..RenderParams::default()
+        };
+        let full = render(&params).unwrap();
+        let mut passes = Vec::new();
+        let last = render_progressive(&params, 8, |image| passes.push(image.clone())).unwrap();
+        assert_eq!(last, full);
+        assert_eq!(passes.len(), 4);
+        assert_eq!(passes[3], full);
+        // The first pass repeats each shaded pixel over its 8 x 8 block.
+        let coarse = &passes[0];
+        for (x, y, pixel) in coarse.enumerate_pixels() {
+            assert_eq!(pixel, full.get_pixel(x - x % 8, y - y % 8));
+        }
+        assert_ne!(passes[0], full);
+    }
+
+    #[test]
+    fn powers_below_two_are_rejected_before_rendering() {
+        for power in [0, 1] {
+            let params = RenderParams {
+                pixel_density: 20,
+                power,
+                ..RenderParams::default()
+            };
+            assert!(matches!(render(&params), Err(MandelbrotError::InvalidParams(_))));
+        }
+    }
+
+    #[test]
+    fn tiles_are_validated_like_renders() {
+        let params = RenderParams::default();
+        assert_eq!(render_tile(&params, 1, 1, 0, 4).unwrap().dimensions(), (4, 4));
+        let err = render_tile(&params, 0, 0, 0, 100_000).unwrap_err();
+        assert!(matches!(err, MandelbrotError::DimensionsTooLarge { .. }), "{err}");
+        let err = render_tile(&params, 0, 0, 0, 0).unwrap_err();
+        assert!(matches!(err, MandelbrotError::InvalidSize { .. }), "{err}");
+
+        let bad_gamma = RenderParams { gamma: -1.0, ..RenderParams::default() };
+        let err = render_tile(&bad_gamma, 0, 0, 0, 4).unwrap_err();
+        assert!(matches!(err, MandelbrotError::InvalidGamma(_)), "{err}");
+        for bad in [
+            RenderParams { bailout: f64::NAN, ..RenderParams::default() },
+            RenderParams { bailout: 0.0, ..RenderParams::default() },
+            RenderParams { smoothing_bailout: Some(-1.0), ..RenderParams::default() },
+        ] {
+            let err = render_tile(&bad, 0, 0, 0, 4).unwrap_err();
+            assert!(matches!(err, MandelbrotError::InvalidParams(_)), "{err}");
+            let bad = RenderParams { pixel_density: 20, ..bad };
+            assert!(matches!(render(&bad), Err(MandelbrotError::InvalidParams(_))));
+        }
+    }
+}
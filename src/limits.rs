@@ -0,0 +1,81 @@
+use crate::{
+    shade_samples, validate, with_threads, EscapeConfig, Fractal, IterationPolicy, MandelbrotError,
+    RenderParams,
+};
+use image::RgbImage;
+use ndarray::Array2;
+use num_complex::Complex;
+
+// `escape_grid` with every point iterated up to its own limit, the entry of
+// `limits` at the same index.
+pub fn escape_grid_with_limits(
+    c: &Array2<Complex<f64>>,
+    limits: &Array2<usize>,
+    fractal: Fractal,
+) -> Result<Array2<Option<usize>>, MandelbrotError> {
+    check_shape(limits, c.dim())?;
+    let escape = |value: &Complex<f64>, &limit: &usize| {
+        fractal.escape_time(value, &EscapeConfig::new(limit))
+    };
+    #[cfg(feature = "parallel")]
+    return Ok(ndarray::Zip::from(c).and(limits).par_map_collect(escape));
+    #[cfg(not(feature = "parallel"))]
+    Ok(ndarray::Zip::from(c).and(limits).map_collect(escape))
+}
+
+// `render` with each pixel's iteration limit taken from `limits`, which holds
+// one entry per grid point laid out like `complex_matrix`, with the first row
+// at `y_min`. `params.iterations` is ignored: colors span the largest limit,
+// so a pixel that escapes renders the same whatever its own limit, and one
+// that doesn't escape within it gets the interior color. Only the `f64`
+// sampler is used.
+pub fn render_with_iteration_limits(
+    params: &RenderParams,
+    limits: &Array2<usize>,
+) -> Result<RgbImage, MandelbrotError> {
+    let largest = limits.iter().copied().max().unwrap_or(0).max(1);
+    let params = &RenderParams {
+        iterations: IterationPolicy::Fixed(largest),
+        ..params.clone()
+    };
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    check_shape(limits, (height, width))?;
+
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    let dx = params.sampling.spacing(params.x_min, params.x_max, width);
+    let dy = params.sampling.spacing(params.y_min, params.y_max, height);
+    let config = params.escape_config();
+    let sample = |i: usize, j: usize, (ox, oy): (f64, f64)| {
+        let point = params.rotated(Complex::new(re[j] + ox * dx, im[i] + oy * dy));
+        let mut config = config;
+        config.max_iter = limits[[i, j]];
+        (point, params.fractal.escape(&point, &config))
+    };
+    with_threads(params.threads, || {
+        shade_samples(
+            params,
+            params.fractal,
+            (width, height),
+            dx.max(dy),
+            params.samples_per_pixel,
+            sample,
+            &mut |_, _| {},
+        )
+    })
+}
+
+fn check_shape(
+    limits: &Array2<usize>,
+    (rows, columns): (usize, usize),
+) -> Result<(), MandelbrotError> {
+    if limits.dim() == (rows, columns) {
+        return Ok(());
+    }
+    let (found_rows, found_columns) = limits.dim();
+    Err(MandelbrotError::SizeMismatch {
+        expected: (columns as u32, rows as u32),
+        found: (found_columns as u32, found_rows as u32),
+    })
+}
@@ -0,0 +1,58 @@
+use crate::{
+    shade_samples, validate, with_threads, MandelbrotError, RenderParams, Viewport, ZOOM_1_HEIGHT,
+};
+use image::RgbImage;
+use num_complex::Complex;
+use std::f64::consts::TAU;
+
+// A `width` x `height` log-polar view around `center` that shows every zoom
+// from `min_zoom` to `max_zoom` at once as concentric rings, unrolled: each
+// column is an angle, counterclockwise from the positive real axis, and each
+// row a circle of radius `ZOOM_1_HEIGHT / 2 / zoom`, with the zoom growing
+// geometrically from `min_zoom` in the top row to `max_zoom` in the bottom
+// one. Every ring thus gets the same number of pixels however small it is.
+//
+// The coloring, fractal, iteration and sampling settings come from `params`;
+// its bounds and size are ignored, and `IterationPolicy::AutoZoom` scales
+// with `max_zoom`. Pixels sample the centres of their cells. Only the `f64`
+// path is used, so zooms past about `1e13` lose detail, and
+// `ColorMode::Distance` measures against the pixel spacing of the bottom row.
+pub fn render_log_polar(
+    params: &RenderParams,
+    center: Complex<f64>,
+    min_zoom: f64,
+    max_zoom: f64,
+    width: u32,
+    height: u32,
+) -> Result<RgbImage, MandelbrotError> {
+    if !(min_zoom > 0.0 && min_zoom < max_zoom && max_zoom.is_finite()) {
+        return Err(MandelbrotError::InvalidZoomRange { min_zoom, max_zoom });
+    }
+    let params = &params
+        .clone()
+        .with_viewport(&Viewport::new(center, max_zoom, width, height));
+    validate(params)?;
+
+    let (w, h) = (width as f64, height as f64);
+    let config = params.escape_config();
+    let sample = |i: usize, j: usize, (ox, oy): (f64, f64)| {
+        // Row 0 is the bottom of the image.
+        let zoom = max_zoom * (min_zoom / max_zoom).powf((i as f64 + 0.5 + oy) / h);
+        let angle = TAU * (j as f64 + 0.5 + ox) / w;
+        let point = center + Complex::from_polar(ZOOM_1_HEIGHT / 2.0 / zoom, angle);
+        (point, params.fractal.escape(&point, &config))
+    };
+    let spacing = TAU * ZOOM_1_HEIGHT / 2.0 / max_zoom / w;
+
+    with_threads(params.threads, || {
+        shade_samples(
+            params,
+            params.fractal,
+            (width as usize, height as usize),
+            spacing,
+            params.samples_per_pixel,
+            sample,
+            &mut |_, _| {},
+        )
+    })
+}
@@ -1,165 +1,263 @@
-use ndarray::{linspace, Array, Array2};
-use num_bigint::{BigInt, ToBigInt};
+use clap::Parser;
+use image::ImageError;
+use mandelbrot::{
+    draw_grid, draw_markers, path_error, prepare_output, render_centroid, render_checkpointed,
+    render_gray16, render_outline, render_svg, render_with_progress, save_with_params, with_legend,
+    write_with_params, GridOverlay, IterationPolicy, Legend, MandelbrotError, Marker, OutputFormat,
+    Palette, RenderParams, MANDELBROT_BOUNDS, MAX_DIMENSION,
+};
 use num_complex::Complex;
-use plotters::prelude::*;
-use plotters::style::{BLACK, WHITE};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-struct Z {
-    z: BigInt,
-    c: BigInt,
-    limit: Option<u128>,
-    count: u128,
+#[derive(Parser)]
+#[command(about = "Renders the Mandelbrot set to an image", allow_negative_numbers = true)]
+struct Args {
+    #[arg(long, default_value_t = MANDELBROT_BOUNDS.0)]
+    x_min: f64,
+    #[arg(long, default_value_t = MANDELBROT_BOUNDS.1)]
+    x_max: f64,
+    #[arg(long, default_value_t = MANDELBROT_BOUNDS.2)]
+    y_min: f64,
+    #[arg(long, default_value_t = MANDELBROT_BOUNDS.3)]
+    y_max: f64,
+    /// Iterations per point before it counts as inside the set; raises detail
+    /// along the boundary without changing the image size
+    #[arg(long, default_value_t = 20)]
+    iterations: usize,
+    /// Scale the iteration limit with zoom instead: BASE at the full view, plus
+    /// BASE more for every doubling of the zoom
+    #[arg(long, value_name = "BASE", conflicts_with = "iterations")]
+    auto_iterations: Option<usize>,
+    /// Pick the iteration limit from the zoom, as `suggest_max_iter` does
+    #[arg(long, conflicts_with_all = ["iterations", "auto_iterations"])]
+    suggest_iterations: bool,
+    /// Samples per unit of the complex plane along each axis, which sets the
+    /// image size without changing the iteration depth
+    #[arg(long, default_value_t = 8000)]
+    density: i128,
+    /// Refuse images wider or taller than this many pixels
+    #[arg(long, default_value_t = MAX_DIMENSION)]
+    max_dimension: u32,
+    /// Render even when the image exceeds --max-dimension
+    #[arg(long)]
+    allow_huge: bool,
+    /// Image file to write, or `-` for stdout
+    #[arg(long, default_value = "mandelbrot.png")]
+    output: PathBuf,
+    /// Output format (png, jpg, bmp or ppm); inferred from the output extension if
+    /// omitted, and png for stdout
+    #[arg(long)]
+    format: Option<OutputFormat>,
+    /// Worker threads to render with; defaults to one per core
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Write a 16-bit grayscale PNG of escape iterations instead of a color image
+    #[arg(long)]
+    gray16: bool,
+    /// Draw only the outline of the set as thin lines instead of filling it
+    #[arg(long, conflicts_with = "gray16")]
+    outline: bool,
+    /// Write the outline of the set as SVG vector paths instead of an image
+    #[arg(long, conflicts_with_all = ["gray16", "outline"])]
+    svg: bool,
+    /// Save progress to this file as rows finish, resuming from it if it exists
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Color the exterior from a gradient file: `position r g b` lines or a GIMP
+    /// .ggr gradient
+    #[arg(long, value_name = "FILE")]
+    palette: Option<PathBuf>,
+    /// Gamma applied to the finished colors; 2.2 brightens midtones for sRGB
+    /// displays
+    #[arg(long, default_value_t = 1.0)]
+    gamma: f64,
+    /// Overlay labelled gridlines every STEP units along both axes
+    #[arg(long, value_name = "STEP", value_parser = parse_step)]
+    grid: Option<f64>,
+    /// Mark the point RE,IM with a crosshair; may be repeated
+    #[arg(long, value_name = "RE,IM", value_parser = parse_point, allow_hyphen_values = true)]
+    marker: Vec<Complex<f64>>,
+    /// Add a color-scale bar labelled with the iteration range in a margin to
+    /// the right of the image
+    #[arg(long)]
+    legend: bool,
+    /// First save a quick draft of the same view at a tenth of the size next to
+    /// the output, as NAME-preview.EXT
+    #[arg(long)]
+    preview: bool,
+    /// Stop after the preview instead of going on to the full render
+    #[arg(long, requires = "preview")]
+    preview_only: bool,
+    /// Print the centre of mass of the set's pixels in the view instead of
+    /// rendering, for centring a zoom
+    #[arg(long)]
+    centroid: bool,
 }
 
-impl Iterator for Z {
-    type Item = BigInt;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let r = self.z.clone();
-        if let Some(limit) = self.limit {
-            if self.count > limit {
-                return None;
-            }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let palette = match &args.palette {
+        Some(path) => Palette::from_file(path)?,
+        None => Palette::default(),
+    };
+    let params = RenderParams {
+        x_min: args.x_min,
+        x_max: args.x_max,
+        y_min: args.y_min,
+        y_max: args.y_max,
+        pixel_density: args.density,
+        max_dimension: args.max_dimension,
+        allow_huge: args.allow_huge,
+        iterations: match args.auto_iterations {
+            _ if args.suggest_iterations => IterationPolicy::Suggested,
+            Some(base) => IterationPolicy::AutoZoom { base },
+            None => IterationPolicy::Fixed(args.iterations),
+        },
+        palette,
+        gamma: args.gamma,
+        threads: args.threads,
+        ..RenderParams::default()
+    };
+    if args.centroid {
+        let centroid = render_centroid(&params)?;
+        println!("Centroid: {} {}", centroid.re, centroid.im);
+        return Ok(());
+    }
+    if args.preview {
+        let preview = preview_path(&args.output);
+        render_to(&args, &params.preview(), &preview, None)?;
+        if args.preview_only {
+            return Ok(());
         }
-
-        self.z = self.z.pow(2) + self.c.clone();
-
-        self.count += 1;
-
-        Some(r)
     }
+    render_to(&args, &params, &args.output, args.checkpoint.as_deref())
 }
 
-#[derive(Default)]
-struct ZBuilder {
-    z: BigInt,
-    candidate: BigInt,
-    limit: Option<u128>,
+// Runs `save`, one of the `image` crate's savers, after creating the
+// missing directories, and names `output` in its I/O errors as the library's
+// own savers do.
+fn save_image(
+    output: &Path,
+    save: impl FnOnce(&Path) -> Result<(), ImageError>,
+) -> Result<(), MandelbrotError> {
+    prepare_output(output)?;
+    save(output).map_err(|err| match err {
+        ImageError::IoError(err) => path_error(output, err),
+        err => MandelbrotError::Image(err),
+    })
 }
 
-impl ZBuilder {
-    fn new() -> Self {
-        ZBuilder::default()
+fn render_to(
+    args: &Args,
+    params: &RenderParams,
+    output: &Path,
+    checkpoint: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if args.gray16 {
+        let image = render_gray16(params)?;
+        save_image(output, |output| image.save(output))?;
+        println!("Plot saved to {}", output.display());
+        return Ok(());
     }
-
-    fn z(self, z: i128) -> Self {
-        ZBuilder {
-            z: z.to_bigint().unwrap(),
-            candidate: self.candidate,
-            limit: self.limit,
-        }
+    if args.outline {
+        let image = render_outline(params)?;
+        save_image(output, |output| image.save(output))?;
+        println!("Plot saved to {}", output.display());
+        return Ok(());
     }
-
-    fn c(self, c: i128) -> Self {
-        ZBuilder {
-            z: self.z,
-            candidate: c.to_bigint().unwrap(),
-            limit: self.limit,
-        }
+    if args.svg {
+        let svg = render_svg(params)?;
+        prepare_output(output)?;
+        std::fs::write(output, svg).map_err(|err| path_error(output, err))?;
+        println!("Plot saved to {}", output.display());
+        return Ok(());
     }
 
-    fn limit(self, limit: u128) -> Self {
-        ZBuilder {
-            z: self.z,
-            candidate: self.candidate,
-            limit: Some(limit),
+    let stdout = output == Path::new("-");
+    let format = match args.format {
+        Some(format) => format,
+        None if stdout => OutputFormat::Png,
+        None => OutputFormat::for_path(output)?,
+    };
+    let mut image = match checkpoint {
+        Some(checkpoint) => render_checkpointed(params, checkpoint)?,
+        None => {
+            let mut last_percent = None;
+            let image = render_with_progress(params, |done, total| {
+                let percent = done * 100 / total;
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    eprint!("\rRendering... {percent}%");
+                    let _ = std::io::stderr().flush();
+                }
+            })?;
+            eprintln!();
+            image
         }
+    };
+    if let Some(step) = args.grid {
+        let grid = GridOverlay {
+            re_step: step,
+            im_step: step,
+            ..GridOverlay::default()
+        };
+        draw_grid(&mut image, params, &grid)?;
     }
-
-    fn build(self) -> Z {
-        Z {
-            z: self.z,
-            c: self.candidate,
-            limit: self.limit,
-            count: 0,
-        }
+    let markers = args
+        .marker
+        .iter()
+        .map(|&point| Marker::new(point))
+        .collect::<Vec<_>>();
+    draw_markers(&mut image, params, &markers);
+    if args.legend {
+        image = with_legend(&image, params, &Legend::default())?;
     }
-}
 
-fn mandelbrot(c: i128) -> ZBuilder {
-    ZBuilder::new().z(0).c(c)
-}
-
-fn julia(c: i128, p: i128) -> ZBuilder {
-    ZBuilder::new().z(c).c(p)
-}
-
-fn complex_matrix(
-    x_min: f64,
-    x_max: f64,
-    y_min: f64,
-    y_max: f64,
-    pixel_density: i128,
-) -> Array2<Complex<f64>> {
-    let re = linspace(x_min, x_max, ((x_max - x_min) * pixel_density as f64) as usize)
-        .collect::<Vec<f64>>();
-    let im = linspace(y_min, y_max, ((y_max - y_min) * pixel_density as f64) as usize)
-        .collect::<Vec<f64>>();
-
-    let re_array = Array::from_vec(re);
-    let im_array = Array::from_vec(im);
-
-    let mut complex_array = Array2::from_elem((im_array.len(), re_array.len()), Complex::new(0.0, 0.0));
-
-    for (i, im_val) in im_array.iter().enumerate() {
-        for (j, re_val) in re_array.iter().enumerate() {
-            complex_array[[i, j]] = Complex::new(*re_val, *im_val);
-        }
+    if stdout {
+        write_with_params(&image, params, std::io::stdout().lock(), format)?;
+        return Ok(());
     }
+    save_with_params(&image, params, output, format)?;
 
-    complex_array
-}
+    println!("Plot saved to {}", output.display());
 
-fn is_stable(c: &Complex<f64>, num_iterations: usize) -> bool {
-    let mut z = Complex::new(0.0, 0.0);
-    for _ in 0..num_iterations {
-        z = z * z + c;
-    }
-    z.norm() <= 2.0
+    Ok(())
 }
 
-fn get_members(c: &Array2<Complex<f64>>, num_iterations: usize) -> Vec<(f64, f64)> {
-    let mut members = Vec::new();
-
-    for &value in c.iter() {
-        if is_stable(&value, num_iterations) {
-            members.push((value.re, value.im));
-        }
+// A grid step, which must be positive and finite.
+fn parse_step(text: &str) -> Result<f64, String> {
+    let step = text
+        .parse::<f64>()
+        .map_err(|err| format!("`{text}`: {err}"))?;
+    if !(step > 0.0 && step.is_finite()) {
+        return Err(format!(
+            "the step must be positive and finite, got `{text}`"
+        ));
     }
-
-    members
+    Ok(step)
 }
 
-fn plot_mandelbrot(members: Vec<(f64, f64)>) -> Result<(), Box<dyn std::error::Error>> {
-    let root = BitMapBackend::new("mandelbrot.png", (20000, 20000)).into_drawing_area();
-    root.fill(&WHITE)?;
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Mandelbrot Set", ("sans-serif", 30).into_font())
-        .margin(5)
-        .x_label_area_size(20)
-        .y_label_area_size(20)
-        .build_cartesian_2d(-2.0..0.5, -1.5..1.5)?;
-
-    chart.configure_mesh().disable_mesh().draw()?;
-
-    chart.draw_series(
-        members
-            .into_iter()
-            .map(|(re, im)| Circle::new((re, im), 1, &BLACK)),
-    )?;
-
-    root.present()?;
-    Ok(())
+// `RE,IM`, e.g. `-0.75,0.1`.
+fn parse_point(text: &str) -> Result<Complex<f64>, String> {
+    let (re, im) = text
+        .split_once(',')
+        .ok_or_else(|| format!("expected RE,IM, got `{text}`"))?;
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<f64>()
+            .map_err(|err| format!("`{part}`: {err}"))
+    };
+    Ok(Complex::new(parse(re)?, parse(im)?))
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let c = complex_matrix(-2.0, 0.5, -1.5, 1.5, 8000);
-    let members = get_members(&c, 20);
-    
-    plot_mandelbrot(members)?;
-
-    println!("Plot saved to mandelbrot.png");
-
-    Ok(())
+// `dir/name.ext` becomes `dir/name-preview.ext`.
+fn preview_path(output: &Path) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{stem}-preview");
+    if let Some(extension) = output.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    output.with_file_name(name)
 }
@@ -0,0 +1,66 @@
+use crate::{complex_to_pixel, RenderParams};
+use image::{Rgb, RgbImage};
+use num_complex::Complex;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarkerShape {
+    // A horizontal and a vertical line crossing at the point.
+    #[default]
+    Crosshair,
+    // A ring around the point.
+    Circle,
+}
+
+// An annotation at one point of the plane, such as a minibrot or a bulb
+// centre. `size` is the half-length of the crosshair's arms or the circle's
+// radius, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Marker {
+    pub point: Complex<f64>,
+    pub shape: MarkerShape,
+    pub color: Rgb<u8>,
+    pub size: u32,
+}
+
+impl Marker {
+    // A white crosshair at `point`.
+    pub fn new(point: Complex<f64>) -> Self {
+        Marker {
+            point,
+            shape: MarkerShape::Crosshair,
+            color: Rgb([255, 255, 255]),
+            size: 6,
+        }
+    }
+}
+
+// Draws every marker onto `image`, which must be the image `render` produced
+// for `params`, at the pixel its point is sampled at, as
+// `Viewport::complex_to_pixel` finds it, rotation included. Markers partly or
+// wholly outside the image are clipped; their shapes stay upright.
+pub fn draw_markers(image: &mut RgbImage, params: &RenderParams, markers: &[Marker]) {
+    let (width, height) = image.dimensions();
+    let viewport = (params.x_min, params.x_max, params.y_min, params.y_max);
+    for marker in markers {
+        let point = params.upright(marker.point);
+        let (px, py) = complex_to_pixel(point, viewport, width as usize, height as usize);
+        let (x, y) = (px.round() as i64, py.round() as i64);
+        let size = marker.size as i64;
+        for dy in -size..=size {
+            for dx in -size..=size {
+                let on = match marker.shape {
+                    MarkerShape::Crosshair => dx == 0 || dy == 0,
+                    // Pixels within half a pixel of the ring.
+                    MarkerShape::Circle => {
+                        let distance = ((dx * dx + dy * dy) as f64).sqrt();
+                        (distance - size as f64).abs() < 0.5
+                    }
+                };
+                let (x, y) = (x + dx, y + dy);
+                if on && (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                    image.put_pixel(x as u32, y as u32, marker.color);
+                }
+            }
+        }
+    }
+}
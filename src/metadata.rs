@@ -0,0 +1,403 @@
+use crate::{
+    ColorMode, ColorScale, Dither, Exterior, Fractal, InteriorMode, IterationPolicy,
+    MandelbrotError, Palette, Precision, PreviewBackground, RenderParams, Sampling, Trap,
+};
+use image::Rgb;
+use num_complex::Complex;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "fs")]
+use std::{fs::File, io::BufReader, path::Path};
+
+// Every key written to PNG text chunks starts with this.
+const PREFIX: &str = "mandelbrot:";
+
+// The `(key, value)` pairs describing `params`: every setting that changes
+// the pixels, the palettes in the form `Palette::key` gives. Values use
+// `Display`, which for `f64` round-trips exactly. `iterations` is the
+// resolved limit, so an `AutoZoom` render reads back as the `Fixed` one that
+// reproduces it. Past the first eight, settings are only listed when they
+// differ from `RenderParams::default()`.
+pub(crate) fn fields(params: &RenderParams) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        ("x_min", params.x_min.to_string()),
+        ("x_max", params.x_max.to_string()),
+        ("y_min", params.y_min.to_string()),
+        ("y_max", params.y_max.to_string()),
+        ("pixel_density", params.pixel_density.to_string()),
+        ("iterations", params.max_iter().to_string()),
+        ("power", params.power.to_string()),
+        ("bailout", params.bailout.to_string()),
+    ];
+    let default = RenderParams::default();
+    if let Some((width, height)) = params.size {
+        fields.push(("size", format!("{width}x{height}")));
+    }
+    if params.sampling != default.sampling {
+        fields.push(("sampling", name(SAMPLINGS, params.sampling)));
+    }
+    if let Some(budget) = params.interior_budget {
+        fields.push(("interior_budget", budget.to_string()));
+    }
+    if params.rotation != 0.0 {
+        fields.push(("rotation", params.rotation.to_string()));
+    }
+    if let Some(bailout) = params.smoothing_bailout {
+        fields.push(("smoothing_bailout", bailout.to_string()));
+    }
+    if params.z0 != default.z0 {
+        fields.push(("z0", complex_text(params.z0)));
+    }
+    if params.fractal != default.fractal {
+        fields.push(("fractal", fractal_text(params.fractal)));
+    }
+    if params.color_mode != default.color_mode {
+        fields.push(("color_mode", color_mode_text(params.color_mode)));
+    }
+    if params.color_scale != default.color_scale {
+        fields.push(("color_scale", name(COLOR_SCALES, params.color_scale)));
+    }
+    if params.palette_offset != default.palette_offset {
+        fields.push(("palette_offset", params.palette_offset.to_string()));
+    }
+    if params.palette != default.palette {
+        fields.push(("palette", params.palette.key()));
+    }
+    if params.interior != default.interior {
+        let [r, g, b] = params.interior.0;
+        fields.push(("interior", format!("{r},{g},{b}")));
+    }
+    if params.interior_mode != default.interior_mode {
+        fields.push(("interior_mode", name(INTERIOR_MODES, params.interior_mode)));
+    }
+    if params.exterior != default.exterior {
+        fields.push(("exterior", name(EXTERIORS, params.exterior)));
+    }
+    if params.interior_palette != default.interior_palette {
+        fields.push(("interior_palette", params.interior_palette.key()));
+    }
+    if params.preview_background != default.preview_background {
+        fields.push((
+            "preview_background",
+            name(BACKGROUNDS, params.preview_background),
+        ));
+    }
+    if params.newton_shading != default.newton_shading {
+        fields.push(("newton_shading", params.newton_shading.to_string()));
+    }
+    if params.gamma != default.gamma {
+        fields.push(("gamma", params.gamma.to_string()));
+    }
+    if params.dither != default.dither {
+        fields.push(("dither", name(DITHERS, params.dither)));
+    }
+    if params.precision != default.precision {
+        fields.push(("precision", precision_text(params.precision)));
+    }
+    if params.samples_per_pixel != default.samples_per_pixel {
+        fields.push(("samples_per_pixel", params.samples_per_pixel.to_string()));
+    }
+    if params.adaptive_sampling != default.adaptive_sampling {
+        fields.push(("adaptive_sampling", params.adaptive_sampling.to_string()));
+    }
+    if params.jitter != default.jitter {
+        fields.push(("jitter", params.jitter.to_string()));
+    }
+    if let Some(seed) = params.seed {
+        fields.push(("seed", seed.to_string()));
+    }
+    fields
+}
+
+// Why `set_field` refused a pair.
+enum FieldError {
+    Unknown,
+    Malformed,
+}
+
+// Sets the setting `key` names from its `fields` value.
+fn set_field(params: &mut RenderParams, key: &str, value: &str) -> Result<(), FieldError> {
+    match key {
+        "x_min" => params.x_min = parse(value)?,
+        "x_max" => params.x_max = parse(value)?,
+        "y_min" => params.y_min = parse(value)?,
+        "y_max" => params.y_max = parse(value)?,
+        "pixel_density" => params.pixel_density = parse(value)?,
+        "iterations" => params.iterations = IterationPolicy::Fixed(parse(value)?),
+        "power" => params.power = parse(value)?,
+        "bailout" => params.bailout = parse(value)?,
+        "size" => {
+            let (width, height) = value.split_once('x').ok_or(FieldError::Malformed)?;
+            params.size = Some((parse(width)?, parse(height)?));
+        }
+        "sampling" => params.sampling = named(SAMPLINGS, value)?,
+        "interior_budget" => params.interior_budget = Some(parse(value)?),
+        "rotation" => params.rotation = parse(value)?,
+        "smoothing_bailout" => params.smoothing_bailout = Some(parse(value)?),
+        "z0" => params.z0 = parse_complex(value)?,
+        "fractal" => params.fractal = parse_fractal(value)?,
+        "color_mode" => params.color_mode = parse_color_mode(value)?,
+        "color_scale" => params.color_scale = named(COLOR_SCALES, value)?,
+        "palette_offset" => params.palette_offset = parse(value)?,
+        "palette" => params.palette = parse_palette(value)?,
+        "interior" => {
+            let channels = numbers::<u8>(value)?;
+            let [r, g, b] = channels[..] else {
+                return Err(FieldError::Malformed);
+            };
+            params.interior = Rgb([r, g, b]);
+        }
+        "interior_mode" => params.interior_mode = named(INTERIOR_MODES, value)?,
+        "exterior" => params.exterior = named(EXTERIORS, value)?,
+        "interior_palette" => params.interior_palette = parse_palette(value)?,
+        "preview_background" => params.preview_background = named(BACKGROUNDS, value)?,
+        "newton_shading" => params.newton_shading = parse(value)?,
+        "gamma" => params.gamma = parse(value)?,
+        "dither" => params.dither = named(DITHERS, value)?,
+        "precision" => params.precision = parse_precision(value)?,
+        "samples_per_pixel" => params.samples_per_pixel = parse(value)?,
+        "adaptive_sampling" => params.adaptive_sampling = parse(value)?,
+        "jitter" => params.jitter = parse(value)?,
+        "seed" => params.seed = Some(parse(value)?),
+        _ => return Err(FieldError::Unknown),
+    }
+    Ok(())
+}
+
+// The values of the settings without any data, by name.
+const SAMPLINGS: &[(Sampling, &str)] = &[
+    (Sampling::Inclusive, "inclusive"),
+    (Sampling::HalfOpen, "half-open"),
+];
+const COLOR_SCALES: &[(ColorScale, &str)] =
+    &[(ColorScale::Linear, "linear"), (ColorScale::Log, "log")];
+const INTERIOR_MODES: &[(InteriorMode, &str)] = &[
+    (InteriorMode::Solid, "solid"),
+    (InteriorMode::Magnitude, "magnitude"),
+    (InteriorMode::Period, "period"),
+];
+const EXTERIORS: &[(Exterior, &str)] = &[
+    (Exterior::Palette, "palette"),
+    (Exterior::Transparent, "transparent"),
+];
+const BACKGROUNDS: &[(PreviewBackground, &str)] = &[
+    (PreviewBackground::Palette, "palette"),
+    (PreviewBackground::Checkerboard, "checkerboard"),
+];
+const DITHERS: &[(Dither, &str)] = &[
+    (Dither::Off, "off"),
+    (Dither::Ordered, "ordered"),
+    (Dither::Random, "random"),
+];
+
+fn name<T: PartialEq>(names: &[(T, &str)], value: T) -> String {
+    let (_, name) = names.iter().find(|(v, _)| *v == value).unwrap();
+    name.to_string()
+}
+
+fn named<T: Copy>(names: &[(T, &str)], text: &str) -> Result<T, FieldError> {
+    let found = names.iter().find(|(_, name)| *name == text);
+    found.map(|&(value, _)| value).ok_or(FieldError::Malformed)
+}
+
+// `RE,IM`.
+fn complex_text(c: Complex<f64>) -> String {
+    format!("{},{}", c.re, c.im)
+}
+
+fn parse_complex(text: &str) -> Result<Complex<f64>, FieldError> {
+    match numbers(text)?[..] {
+        [re, im] => Ok(Complex::new(re, im)),
+        _ => Err(FieldError::Malformed),
+    }
+}
+
+// The comma-separated numbers of `text`.
+fn numbers<T: FromStr>(text: &str) -> Result<Vec<T>, FieldError> {
+    text.split(',').map(parse).collect()
+}
+
+fn parse_palette(text: &str) -> Result<Palette, FieldError> {
+    Palette::from_key(text).map_err(|_| FieldError::Malformed)
+}
+
+// `mandelbrot`, `julia:RE,IM`, `burning-ship`, `tricorn` or `newton`.
+fn fractal_text(fractal: Fractal) -> String {
+    match fractal {
+        Fractal::Mandelbrot => "mandelbrot".to_string(),
+        Fractal::Julia(c) => format!("julia:{}", complex_text(c)),
+        Fractal::BurningShip => "burning-ship".to_string(),
+        Fractal::Tricorn => "tricorn".to_string(),
+        Fractal::Newton => "newton".to_string(),
+    }
+}
+
+fn parse_fractal(text: &str) -> Result<Fractal, FieldError> {
+    Ok(match text.split_once(':') {
+        Some(("julia", c)) => Fractal::Julia(parse_complex(c)?),
+        Some(_) => return Err(FieldError::Malformed),
+        None => match text {
+            "mandelbrot" => Fractal::Mandelbrot,
+            "burning-ship" => Fractal::BurningShip,
+            "tricorn" => Fractal::Tricorn,
+            "newton" => Fractal::Newton,
+            _ => return Err(FieldError::Malformed),
+        },
+    })
+}
+
+// The mode's name, followed for the trap modes by `:` and the trap as
+// `point:RE,IM`, `line:RE,IM,ANGLE` or `cross:RE,IM`.
+fn color_mode_text(mode: ColorMode) -> String {
+    let trap_text = |trap: Trap| match trap {
+        Trap::Point(c) => format!("point:{}", complex_text(c)),
+        Trap::Line { through, angle } => format!("line:{},{angle}", complex_text(through)),
+        Trap::Cross(c) => format!("cross:{}", complex_text(c)),
+    };
+    match mode {
+        ColorMode::Linear => "linear".to_string(),
+        ColorMode::Smooth => "smooth".to_string(),
+        ColorMode::Histogram => "histogram".to_string(),
+        ColorMode::OrbitTrap(trap) => format!("orbit-trap:{}", trap_text(trap)),
+        ColorMode::Distance => "distance".to_string(),
+        ColorMode::Potential => "potential".to_string(),
+        ColorMode::Composite { trap } => format!("composite:{}", trap_text(trap)),
+    }
+}
+
+fn parse_color_mode(text: &str) -> Result<ColorMode, FieldError> {
+    let parse_trap = |text: &str| -> Result<Trap, FieldError> {
+        let (kind, values) = text.split_once(':').ok_or(FieldError::Malformed)?;
+        Ok(match (kind, &numbers::<f64>(values)?[..]) {
+            ("point", &[re, im]) => Trap::Point(Complex::new(re, im)),
+            ("line", &[re, im, angle]) => Trap::Line {
+                through: Complex::new(re, im),
+                angle,
+            },
+            ("cross", &[re, im]) => Trap::Cross(Complex::new(re, im)),
+            _ => return Err(FieldError::Malformed),
+        })
+    };
+    Ok(match text.split_once(':') {
+        Some(("orbit-trap", trap)) => ColorMode::OrbitTrap(parse_trap(trap)?),
+        Some(("composite", trap)) => ColorMode::Composite {
+            trap: parse_trap(trap)?,
+        },
+        Some(_) => return Err(FieldError::Malformed),
+        None => match text {
+            "linear" => ColorMode::Linear,
+            "smooth" => ColorMode::Smooth,
+            "histogram" => ColorMode::Histogram,
+            "distance" => ColorMode::Distance,
+            "potential" => ColorMode::Potential,
+            _ => return Err(FieldError::Malformed),
+        },
+    })
+}
+
+// `auto`, `double`, `arbitrary:BITS` or `perturbation`.
+fn precision_text(precision: Precision) -> String {
+    match precision {
+        Precision::Auto => "auto".to_string(),
+        Precision::Double => "double".to_string(),
+        Precision::Arbitrary { bits } => format!("arbitrary:{bits}"),
+        Precision::Perturbation => "perturbation".to_string(),
+    }
+}
+
+fn parse_precision(text: &str) -> Result<Precision, FieldError> {
+    Ok(match text {
+        "auto" => Precision::Auto,
+        "double" => Precision::Double,
+        "perturbation" => Precision::Perturbation,
+        _ => match text.split_once(':') {
+            Some(("arbitrary", bits)) => Precision::Arbitrary { bits: parse(bits)? },
+            _ => return Err(FieldError::Malformed),
+        },
+    })
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, FieldError> {
+    value.parse().map_err(|_| FieldError::Malformed)
+}
+
+pub(crate) fn params_text(params: &RenderParams) -> Vec<(String, String)> {
+    fields(params)
+        .into_iter()
+        .map(|(key, value)| (format!("{PREFIX}{key}"), value))
+        .collect()
+}
+
+// The same settings `save_with_params` records, as space-separated
+// `key=value` pairs, e.g. for logging the parameters of a render. `FromStr`
+// parses the string back.
+impl fmt::Display for RenderParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (k, (key, value)) in fields(self).into_iter().enumerate() {
+            if k > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+// Parses the `Display` form: whitespace-separated `key=value` pairs, in any
+// order, each setting at most once. Settings left out keep their defaults.
+impl FromStr for RenderParams {
+    type Err = MandelbrotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| MandelbrotError::InvalidParams(message);
+        let mut params = RenderParams::default();
+        let mut seen = Vec::new();
+        for pair in s.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected key=value, got `{pair}`")))?;
+            if seen.contains(&key) {
+                return Err(invalid(format!("`{key}` is given twice")));
+            }
+            seen.push(key);
+            set_field(&mut params, key, value).map_err(|err| match err {
+                FieldError::Unknown => invalid(format!("unknown setting `{key}`")),
+                FieldError::Malformed => invalid(format!("malformed {key} value `{value}`")),
+            })?;
+        }
+        Ok(params)
+    }
+}
+
+// Recovers the parameters saved by `save_with_params`. Settings the file
+// doesn't record keep their defaults.
+#[cfg(feature = "fs")]
+pub fn read_params(path: &Path) -> Result<RenderParams, MandelbrotError> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let reader = decoder
+        .read_info()
+        .map_err(|err| MandelbrotError::Metadata(err.to_string()))?;
+    let chunks = &reader.info().uncompressed_latin1_text;
+
+    let mut params = RenderParams::default();
+    let mut found = false;
+    for chunk in chunks {
+        let Some(key) = chunk.keyword.strip_prefix(PREFIX) else {
+            continue;
+        };
+        found = true;
+        let value = chunk.text.as_str();
+        if let Err(FieldError::Malformed) = set_field(&mut params, key, value) {
+            return Err(MandelbrotError::Metadata(format!(
+                "malformed {PREFIX}{key} value `{value}`"
+            )));
+        }
+    }
+    if !found {
+        return Err(MandelbrotError::Metadata(format!(
+            "{} has no render parameters",
+            path.display()
+        )));
+    }
+    Ok(params)
+}
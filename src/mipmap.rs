@@ -0,0 +1,37 @@
+use crate::{render, MandelbrotError, RenderParams};
+use image::{Rgb, RgbImage};
+
+// The image `render` produces for `params` followed by `levels` mipmaps of
+// it, each half the width and height of the one before, rounded up. Every
+// mipmap pixel is the box-filtered average of the (up to) 2 x 2 pixels it
+// covers, so previews come out antialiased without rendering again. Images
+// stop shrinking once they are 1 x 1.
+pub fn render_with_mipmaps(
+    params: &RenderParams,
+    levels: usize,
+) -> Result<Vec<RgbImage>, MandelbrotError> {
+    let mut images = vec![render(params)?];
+    for _ in 0..levels {
+        let next = halve(images.last().unwrap());
+        images.push(next);
+    }
+    Ok(images)
+}
+
+fn halve(image: &RgbImage) -> RgbImage {
+    let (width, height) = image.dimensions();
+    RgbImage::from_fn(width.div_ceil(2), height.div_ceil(2), |x, y| {
+        let (mut sum, mut count) = ([0u32; 3], 0);
+        for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let (px, py) = (2 * x + sx, 2 * y + sy);
+            if px < width && py < height {
+                let pixel = image.get_pixel(px, py);
+                for (total, &c) in sum.iter_mut().zip(&pixel.0) {
+                    *total += c as u32;
+                }
+                count += 1;
+            }
+        }
+        Rgb(sum.map(|total| ((total + count / 2) / count) as u8))
+    })
+}
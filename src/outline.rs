@@ -0,0 +1,59 @@
+use crate::{boundary_grid, validate, with_threads, MandelbrotError, RenderParams};
+use image::RgbImage;
+use ndarray::Array2;
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Line art of the set: the `boundary_grid` of every pixel's membership drawn
+// in `params.interior` over the start of `params.palette`, the color of an
+// immediate escape. As in `render_gray16`, the sampling is always one `f64`
+// point per pixel and the other coloring settings are ignored.
+pub fn render_outline(params: &RenderParams) -> Result<RgbImage, MandelbrotError> {
+    let members = membership(params)?;
+    let (height, width) = members.dim();
+
+    // The grid's first row is `y_min`, at the bottom of the image.
+    let edges = boundary_grid(&members);
+    let (line, background) = (params.interior, params.palette.color(0.0));
+    Ok(RgbImage::from_fn(
+        width as u32,
+        height as u32,
+        |x, y| match edges[[height - 1 - y as usize, x as usize]] {
+            true => line,
+            false => background,
+        },
+    ))
+}
+
+// Whether each grid point of the window in `params` is in the set, from one
+// `f64` sample each, with the first row at `y_min`.
+pub(crate) fn membership(params: &RenderParams) -> Result<Array2<bool>, MandelbrotError> {
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    let config = params.escape_config();
+
+    let fill = |(i, row): (usize, &mut [bool])| {
+        for (j, member) in row.iter_mut().enumerate() {
+            let point = params.rotated(Complex::new(re[j], im[i]));
+            *member = params.fractal.escape(&point, &config).is_none();
+        }
+    };
+
+    let mut members = vec![false; width * height];
+    if !members.is_empty() {
+        with_threads(params.threads, || {
+            #[cfg(feature = "parallel")]
+            members
+                .par_chunks_exact_mut(width)
+                .enumerate()
+                .for_each(fill);
+            #[cfg(not(feature = "parallel"))]
+            members.chunks_exact_mut(width).enumerate().for_each(fill);
+        })?;
+    }
+    Ok(Array2::from_shape_vec((height, width), members)
+        .expect("buffer holds width * height points"))
+}
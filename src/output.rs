@@ -0,0 +1,279 @@
+use crate::metadata::params_text;
+use crate::{render_fractal, MandelbrotError, RenderParams};
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::error::{EncodingError, ImageFormatHint};
+use image::{ColorType, ImageError, ImageFormat, ImageOutputFormat, RgbImage};
+#[cfg(feature = "fs")]
+use ndarray::Array2;
+#[cfg(feature = "fs")]
+use ndarray_npy::WriteNpyError;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::io::{self, Cursor, ErrorKind, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// JPEG quality, out of 100, used when saving `OutputFormat::Jpeg`.
+pub const JPEG_QUALITY: u8 = 90;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Ppm,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        extension.parse().ok()
+    }
+
+    // Like `from_path`, but an unknown or missing extension is an error.
+    pub fn for_path(path: &Path) -> Result<Self, MandelbrotError> {
+        OutputFormat::from_path(path).ok_or_else(|| {
+            let extension = path.extension().unwrap_or_default();
+            MandelbrotError::UnsupportedFormat(extension.to_string_lossy().into_owned())
+        })
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(OutputFormat::Png),
+            "jpg" | "jpeg" => Ok(OutputFormat::Jpeg),
+            "bmp" => Ok(OutputFormat::Bmp),
+            "ppm" => Ok(OutputFormat::Ppm),
+            _ => Err(format!("unknown output format `{s}`")),
+        }
+    }
+}
+
+// Writes `pixels` (packed RGB, row-major, top row first) as a binary P6 PPM
+// without going through any image encoder.
+#[cfg(feature = "fs")]
+pub fn write_ppm(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> Result<(), MandelbrotError> {
+    let file = File::create(path).map_err(|err| path_error(path, err))?;
+    let mut writer = BufWriter::new(file);
+    encode_ppm(pixels, width, height, &mut writer)
+        .and_then(|()| writer.flush())
+        .map_err(|err| path_error(path, err))
+}
+
+// A buffer that isn't `width * height` RGB pixels is `ErrorKind::InvalidInput`.
+pub fn encode_ppm<W: Write>(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    writer: &mut W,
+) -> io::Result<()> {
+    let expected = width as usize * height as usize * 3;
+    if pixels.len() != expected {
+        let message = format!(
+            "a {width}x{height} RGB image needs {expected} bytes, got {}",
+            pixels.len()
+        );
+        return Err(io::Error::new(ErrorKind::InvalidInput, message));
+    }
+    write!(writer, "P6\n{width} {height}\n255\n")?;
+    writer.write_all(pixels)
+}
+
+// Picks the format from the file extension (`png`, `jpg` or `jpeg`, `bmp` or
+// `ppm`, in any case); anything else is `MandelbrotError::UnsupportedFormat`.
+#[cfg(feature = "fs")]
+pub fn save(image: &RgbImage, path: &Path) -> Result<(), MandelbrotError> {
+    save_as(image, path, OutputFormat::for_path(path)?)
+}
+
+// Missing parent directories are created first; see `prepare_output`.
+#[cfg(feature = "fs")]
+pub fn save_as(image: &RgbImage, path: &Path, format: OutputFormat) -> Result<(), MandelbrotError> {
+    prepare_output(path)?;
+    write_as(image, path, format)
+}
+
+// `save_as` once the directories exist.
+#[cfg(feature = "fs")]
+fn write_as(image: &RgbImage, path: &Path, format: OutputFormat) -> Result<(), MandelbrotError> {
+    match format {
+        OutputFormat::Png => {
+            fs::write(path, encode_png(image)?).map_err(|err| path_error(path, err))?
+        }
+        OutputFormat::Jpeg => write_image(image, path, ImageOutputFormat::Jpeg(JPEG_QUALITY))?,
+        OutputFormat::Bmp => write_image(image, path, ImageOutputFormat::Bmp)?,
+        OutputFormat::Ppm => write_ppm(image, image.width(), image.height(), path)?,
+    }
+    Ok(())
+}
+
+#[cfg(feature = "fs")]
+fn write_image(
+    image: &RgbImage,
+    path: &Path,
+    format: ImageOutputFormat,
+) -> Result<(), MandelbrotError> {
+    let file = File::create(path).map_err(|err| path_error(path, err))?;
+    let mut writer = BufWriter::new(file);
+    image
+        .write_to(&mut writer, format)
+        .map_err(|err| match err {
+            ImageError::IoError(err) => path_error(path, err),
+            err => MandelbrotError::Image(err),
+        })?;
+    writer.flush().map_err(|err| path_error(path, err))
+}
+
+pub fn encode_png(image: &RgbImage) -> Result<Vec<u8>, MandelbrotError> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageOutputFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+// Like `save_as`, but PNGs also carry `params` as `mandelbrot:*` text chunks
+// so `read_params` can recover them. The other formats are saved without.
+#[cfg(feature = "fs")]
+pub fn save_with_params(
+    image: &RgbImage,
+    params: &RenderParams,
+    path: &Path,
+    format: OutputFormat,
+) -> Result<(), MandelbrotError> {
+    prepare_output(path)?;
+    match format {
+        OutputFormat::Png => {
+            let bytes = encode_png_with_params(image, params)?;
+            fs::write(path, bytes).map_err(|err| path_error(path, err))?
+        }
+        format => write_as(image, path, format)?,
+    }
+    Ok(())
+}
+
+// Creates the missing parent directories of `path`. The file itself is only
+// created by the write that follows, whose errors also name `path`, so an
+// encoder that fails first leaves nothing behind.
+#[cfg(feature = "fs")]
+pub fn prepare_output(path: &Path) -> Result<(), MandelbrotError> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(|err| path_error(parent, err))?;
+    }
+    Ok(())
+}
+
+// `MandelbrotError::Io` for `err`, of the same kind, naming `path`.
+#[cfg(feature = "fs")]
+pub fn path_error(path: &Path, err: io::Error) -> MandelbrotError {
+    let message = format!("{}: {err}", path.display());
+    MandelbrotError::Io(io::Error::new(err.kind(), message))
+}
+
+// Like `save_with_params`, but encodes into any writer, such as stdout or a
+// socket, instead of a file. Failures to write are `MandelbrotError::Io`.
+pub fn write_with_params<W: Write>(
+    image: &RgbImage,
+    params: &RenderParams,
+    mut writer: W,
+    format: OutputFormat,
+) -> Result<(), MandelbrotError> {
+    let (width, height) = image.dimensions();
+    match format {
+        OutputFormat::Png => write_png_with_params(image, params, &mut writer)?,
+        OutputFormat::Jpeg => JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY)
+            .encode(image, width, height, ColorType::Rgb8)
+            .map_err(image_error)?,
+        OutputFormat::Bmp => BmpEncoder::new(&mut writer)
+            .encode(image, width, height, ColorType::Rgb8)
+            .map_err(image_error)?,
+        OutputFormat::Ppm => encode_ppm(image, width, height, &mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn encode_png_with_params(
+    image: &RgbImage,
+    params: &RenderParams,
+) -> Result<Vec<u8>, MandelbrotError> {
+    let mut bytes = Vec::new();
+    write_png_with_params(image, params, &mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_png_with_params<W: Write>(
+    image: &RgbImage,
+    params: &RenderParams,
+    writer: W,
+) -> Result<(), MandelbrotError> {
+    let mut encoder = png::Encoder::new(writer, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (key, value) in params_text(params) {
+        encoder.add_text_chunk(key, value).map_err(png_error)?;
+    }
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(image).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+    Ok(())
+}
+
+fn png_error(err: png::EncodingError) -> MandelbrotError {
+    match err {
+        png::EncodingError::IoError(err) => MandelbrotError::Io(err),
+        err => MandelbrotError::Image(ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Png),
+            err,
+        ))),
+    }
+}
+
+fn image_error(err: ImageError) -> MandelbrotError {
+    match err {
+        ImageError::IoError(err) => MandelbrotError::Io(err),
+        err => MandelbrotError::Image(err),
+    }
+}
+
+// Renders straight to an in-memory PNG, e.g. for serving over HTTP. The
+// parameters are embedded as with `save_with_params`.
+pub fn render_png_bytes(params: &RenderParams) -> Result<Vec<u8>, MandelbrotError> {
+    let image = render_fractal(params, params.fractal, &mut |_, _| {})?;
+    encode_png_with_params(&image, params)
+}
+
+// Renders and encodes straight into `writer`, e.g. stdout for piping into
+// another program, as `write_with_params` does.
+pub fn render_to_writer<W: Write>(
+    params: &RenderParams,
+    writer: W,
+    format: OutputFormat,
+) -> Result<(), MandelbrotError> {
+    let image = render_fractal(params, params.fractal, &mut |_, _| {})?;
+    write_with_params(&image, params, writer, format)
+}
+
+// Writes `grid`, e.g. from `escape_counts`, as a NumPy `.npy` file of `<u4`
+// values that loads with `numpy.load`. Rows stay in grid order, so row 0 is
+// `y_min` rather than the top of an image.
+#[cfg(feature = "fs")]
+pub fn write_npy(grid: &Array2<u32>, path: &Path) -> Result<(), MandelbrotError> {
+    ndarray_npy::write_npy(path, grid).map_err(|err| match err {
+        WriteNpyError::Io(err) => MandelbrotError::Io(err),
+        err => MandelbrotError::Io(io::Error::other(err)),
+    })
+}
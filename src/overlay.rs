@@ -0,0 +1,184 @@
+use crate::plot::plot_error;
+use crate::{gamma_table, MandelbrotError, RenderParams};
+use image::{Rgb, RgbImage};
+use plotters::prelude::*;
+
+const LABEL_SIZE: u32 = 12;
+// Gap between a gridline and its label, in pixels.
+const LABEL_PAD: i32 = 2;
+// Gap between the image and the legend's bar, in pixels.
+const LEGEND_GAP: u32 = 4;
+
+// Gridlines over a rendered image at every multiple of `re_step` along the
+// real axis and `im_step` along the imaginary one, optionally labelled with
+// their values. The lines through 0, when visible, are the axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridOverlay {
+    pub re_step: f64,
+    pub im_step: f64,
+    pub color: Rgb<u8>,
+    pub labels: bool,
+}
+
+impl Default for GridOverlay {
+    fn default() -> Self {
+        GridOverlay {
+            re_step: 0.5,
+            im_step: 0.5,
+            color: Rgb([128, 128, 128]),
+            labels: true,
+        }
+    }
+}
+
+// Draws `grid` onto `image`, which must be the image `render` produced for
+// `params`, so that lines land on the samples with those coordinates. Rotated
+// renders, whose gridlines would run at an angle, are refused.
+pub fn draw_grid(
+    image: &mut RgbImage,
+    params: &RenderParams,
+    grid: &GridOverlay,
+) -> Result<(), MandelbrotError> {
+    if params.rotation != 0.0 {
+        let message = format!("grids need an unrotated render, got {}", params.rotation);
+        return Err(MandelbrotError::InvalidParams(message));
+    }
+    for step in [grid.re_step, grid.im_step] {
+        if !(step > 0.0 && step.is_finite()) {
+            let message = format!("grid steps must be positive and finite, got {step}");
+            return Err(MandelbrotError::InvalidParams(message));
+        }
+    }
+    let (width, height) = image.dimensions();
+    if width < 2 || height < 2 {
+        return Ok(());
+    }
+    let dx = params
+        .sampling
+        .spacing(params.x_min, params.x_max, width as usize);
+    let dy = params
+        .sampling
+        .spacing(params.y_min, params.y_max, height as usize);
+    let [r, g, b] = grid.color.0;
+    let color = RGBColor(r, g, b);
+    let font = ("sans-serif", LABEL_SIZE).into_font().color(&color);
+
+    let root = BitMapBackend::with_buffer(image, (width, height)).into_drawing_area();
+    let (w, h) = (width as i32, height as i32);
+    for (re, label) in multiples(params.x_min, params.x_max, grid.re_step) {
+        let x = ((re - params.x_min) / dx).round() as i32;
+        root.draw(&PathElement::new([(x, 0), (x, h - 1)], color))
+            .map_err(plot_error)?;
+        if grid.labels {
+            let position = (x + LABEL_PAD, h - LABEL_SIZE as i32 - LABEL_PAD);
+            root.draw(&Text::new(label, position, &font))
+                .map_err(plot_error)?;
+        }
+    }
+    for (im, label) in multiples(params.y_min, params.y_max, grid.im_step) {
+        let y = h - 1 - ((im - params.y_min) / dy).round() as i32;
+        root.draw(&PathElement::new([(0, y), (w - 1, y)], color))
+            .map_err(plot_error)?;
+        if grid.labels {
+            let position = (LABEL_PAD, y + LABEL_PAD);
+            root.draw(&Text::new(format!("{label}i"), position, &font))
+                .map_err(plot_error)?;
+        }
+    }
+    root.present().map_err(plot_error)?;
+    Ok(())
+}
+
+// Every multiple of `step` in `min..=max` together with its label, written
+// with just enough decimals for `step`.
+fn multiples(min: f64, max: f64, step: f64) -> Vec<(f64, String)> {
+    let decimals = (0..12)
+        .find(|&d| {
+            let scaled = step * 10f64.powi(d);
+            (scaled - scaled.round()).abs() < 1e-9 * scaled.max(1.0)
+        })
+        .unwrap_or(12) as usize;
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last)
+        .map(|k| {
+            let value = k as f64 * step;
+            // `+ 0.0` turns `-0.0` into `0.0`, so the axis is labelled "0".
+            (value, format!("{:.*}", decimals, value + 0.0))
+        })
+        .collect()
+}
+
+// A color scale in a margin added to the right of a rendered image: a bar
+// running through the palette from 0 iterations at the bottom to the
+// iteration limit at the top, as `ColorMode::Linear` and `color_scale` map
+// them, optionally labelled with both counts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Legend {
+    // Width of the added margin, which holds the bar and its labels.
+    pub margin: u32,
+    pub bar_width: u32,
+    pub background: Rgb<u8>,
+    pub label_color: Rgb<u8>,
+    pub labels: bool,
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Legend {
+            margin: 64,
+            bar_width: 16,
+            background: Rgb([0, 0, 0]),
+            label_color: Rgb([255, 255, 255]),
+            labels: true,
+        }
+    }
+}
+
+// `image`, which must be the image `render` produced for `params`, widened by
+// `legend.margin` with the legend drawn in the new strip. The bar shares the
+// render's palette, color scale, palette offset and gamma.
+pub fn with_legend(
+    image: &RgbImage,
+    params: &RenderParams,
+    legend: &Legend,
+) -> Result<RgbImage, MandelbrotError> {
+    let (width, height) = image.dimensions();
+    let mut out = RgbImage::from_pixel(width + legend.margin, height, legend.background);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        out.put_pixel(x, y, *pixel);
+    }
+    if height == 0 {
+        return Ok(out);
+    }
+
+    let max_iter = params.max_iter();
+    let gamma = gamma_table(params.gamma);
+    let bar = width + LEGEND_GAP..(width + LEGEND_GAP + legend.bar_width).min(out.width());
+    for y in 0..height {
+        let t = 1.0 - y as f64 / (height - 1).max(1) as f64;
+        let mut color = params.palette.color(params.palette_position(t));
+        if let Some(table) = &gamma {
+            color.0 = color.0.map(|c| table[c as usize]);
+        }
+        for x in bar.clone() {
+            out.put_pixel(x, y, color);
+        }
+    }
+
+    if legend.labels {
+        let [r, g, b] = legend.label_color.0;
+        let color = RGBColor(r, g, b);
+        let font = ("sans-serif", LABEL_SIZE).into_font().color(&color);
+        let x = bar.end as i32 + LABEL_PAD;
+        let bottom = height as i32 - LABEL_SIZE as i32;
+        let size = out.dimensions();
+        let root = BitMapBackend::with_buffer(&mut out, size).into_drawing_area();
+        root.draw(&Text::new(max_iter.to_string(), (x, 0), &font))
+            .map_err(plot_error)?;
+        root.draw(&Text::new("0", (x, bottom), &font))
+            .map_err(plot_error)?;
+        root.present().map_err(plot_error)?;
+    }
+    Ok(out)
+}
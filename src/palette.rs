@@ -0,0 +1,446 @@
+use crate::MandelbrotError;
+use image::Rgb;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+// The first line of a GIMP gradient (`.ggr`) file.
+const GGR_HEADER: &str = "GIMP Gradient";
+
+// A gradient over the normalized escape value in `[0, 1]`, given as color
+// stops sorted by position and interpolated linearly between neighbours in
+// `space`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    stops: Vec<(f64, Rgb<u8>)>,
+    space: ColorSpace,
+}
+
+// Where `Palette` blends neighbouring stops. Stops are always given in sRGB
+// and converted at lookup time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorSpace {
+    // Straight per-channel blending, which dulls the midtones between
+    // saturated colors.
+    #[default]
+    Rgb,
+    // Hue, saturation and lightness, taking the shorter way around the hue
+    // circle, so saturated stops stay saturated in between.
+    Hsl,
+    // CIE L*a*b* (D65), where equal steps look roughly equally different.
+    Lab,
+}
+
+// The JSON form of a `Palette`: its stops as `[position, [r, g, b]]` pairs
+// and its color space.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PaletteJson {
+    stops: Vec<(f64, [u8; 3])>,
+    #[serde(default)]
+    space: ColorSpace,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Palette {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = PaletteJson {
+            stops: self.stops.iter().map(|&(t, color)| (t, color.0)).collect(),
+            space: self.space,
+        };
+        json.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Palette {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = PaletteJson::deserialize(deserializer)?;
+        if json.stops.is_empty() {
+            return Err(serde::de::Error::custom(
+                "a palette needs at least one color stop",
+            ));
+        }
+        let stops = json
+            .stops
+            .into_iter()
+            .map(|(t, color)| (t, Rgb(color)))
+            .collect();
+        Ok(Palette::new(stops).with_space(json.space))
+    }
+}
+
+impl Palette {
+    pub fn new(mut stops: Vec<(f64, Rgb<u8>)>) -> Self {
+        assert!(!stops.is_empty(), "a palette needs at least one color stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Palette {
+            stops,
+            space: ColorSpace::Rgb,
+        }
+    }
+
+    pub fn with_space(self, space: ColorSpace) -> Self {
+        Palette { space, ..self }
+    }
+
+    pub fn solid(color: Rgb<u8>) -> Self {
+        Palette::new(vec![(0.0, color)])
+    }
+
+    pub fn grayscale() -> Self {
+        Palette::new(vec![(0.0, Rgb([0, 0, 0])), (1.0, Rgb([255, 255, 255]))])
+    }
+
+    pub fn blue_white() -> Self {
+        Palette::new(vec![
+            (0.0, Rgb([0, 7, 100])),
+            (0.4, Rgb([32, 107, 203])),
+            (0.8, Rgb([237, 255, 255])),
+            (1.0, Rgb([255, 255, 255])),
+        ])
+    }
+
+    pub fn fire() -> Self {
+        Palette::new(vec![
+            (0.0, Rgb([0, 0, 0])),
+            (0.3, Rgb([128, 0, 0])),
+            (0.55, Rgb([255, 64, 0])),
+            (0.8, Rgb([255, 200, 0])),
+            (1.0, Rgb([255, 255, 255])),
+        ])
+    }
+
+    // Reads a gradient in either format `parse` accepts.
+    #[cfg(feature = "fs")]
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, MandelbrotError> {
+        Palette::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // A GIMP gradient when `text` starts with `GIMP Gradient`, otherwise one
+    // `position r g b` stop per line, with positions in `[0, 1]` and channels
+    // in `0..=255`. Blank lines and lines starting with `#` are skipped. In
+    // both formats positions must never decrease.
+    //
+    // Each GIMP segment becomes stops at its two ends and its midpoint, so
+    // curved blending and HSV segments come out as straight RGB blends, and
+    // alpha is dropped.
+    pub fn parse(text: &str) -> Result<Self, MandelbrotError> {
+        let numbered = || {
+            text.lines()
+                .enumerate()
+                .map(|(k, line)| (k + 1, line.trim()))
+        };
+        let mut lines = numbered();
+        let stops = match lines.next() {
+            Some((_, GGR_HEADER)) => parse_ggr(lines)?,
+            _ => parse_stops(numbered())?,
+        };
+        if stops.is_empty() {
+            return Err(MandelbrotError::InvalidPalette(
+                "no color stops".to_string(),
+            ));
+        }
+        if let Some(pair) = stops.windows(2).find(|pair| pair[1].0 < pair[0].0) {
+            return Err(MandelbrotError::InvalidPalette(format!(
+                "position {} comes after {}",
+                pair[1].0, pair[0].0
+            )));
+        }
+        Ok(Palette::new(stops))
+    }
+
+    // One whitespace-free token: the stops as `position:r,g,b` joined by `/`,
+    // then `@` and the color space, e.g. `0:0,0,0/1:255,255,255@rgb`. It
+    // covers everything that changes the colors, and `from_key` reads it back.
+    pub(crate) fn key(&self) -> String {
+        let stops = self
+            .stops
+            .iter()
+            .map(|(t, Rgb([r, g, b]))| format!("{t}:{r},{g},{b}"))
+            .collect::<Vec<_>>();
+        let space = match self.space {
+            ColorSpace::Rgb => "rgb",
+            ColorSpace::Hsl => "hsl",
+            ColorSpace::Lab => "lab",
+        };
+        format!("{}@{space}", stops.join("/"))
+    }
+
+    // Checks the stops as `parse` does.
+    pub(crate) fn from_key(key: &str) -> Result<Self, MandelbrotError> {
+        let invalid = |message: String| MandelbrotError::InvalidPalette(message);
+        let (stops, space) = key
+            .rsplit_once('@')
+            .ok_or_else(|| invalid(format!("`{key}` has no color space")))?;
+        let space = match space {
+            "rgb" => ColorSpace::Rgb,
+            "hsl" => ColorSpace::Hsl,
+            "lab" => ColorSpace::Lab,
+            _ => return Err(invalid(format!("unknown color space `{space}`"))),
+        };
+        let lines = stops
+            .split('/')
+            .map(|stop| stop.replace([':', ','], " "))
+            .collect::<Vec<_>>();
+        Ok(Palette::parse(&lines.join("\n"))?.with_space(space))
+    }
+
+    pub fn color(&self, t: f64) -> Rgb<u8> {
+        Rgb(self.exact_color(t).map(|c| c.round() as u8))
+    }
+
+    // The color at `t` before it is rounded to 8 bits, each channel in
+    // `0.0..=255.0`; `color` rounds it to the nearest value.
+    pub fn exact_color(&self, t: f64) -> [f64; 3] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let upper = self.stops.partition_point(|(position, _)| *position < t);
+        if upper == 0 {
+            return self.stops[0].1 .0.map(f64::from);
+        }
+        if upper == self.stops.len() {
+            return self.stops[upper - 1].1 .0.map(f64::from);
+        }
+
+        let (p0, c0) = self.stops[upper - 1];
+        let (p1, c1) = self.stops[upper];
+        let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 0.0 };
+        match self.space {
+            ColorSpace::Rgb => std::array::from_fn(|i| lerp(c0.0[i] as f64, c1.0[i] as f64, f)),
+            ColorSpace::Hsl => {
+                let ([h0, s0, l0], [h1, s1, l1]) = (to_hsl(c0), to_hsl(c1));
+                // A gray has no hue of its own, so it takes its neighbour's.
+                let (h0, h1) = match (s0 == 0.0, s1 == 0.0) {
+                    (true, false) => (h1, h1),
+                    (false, true) => (h0, h0),
+                    _ => (h0, h1),
+                };
+                let dh = (h1 - h0 + 540.0) % 360.0 - 180.0;
+                from_hsl([
+                    (h0 + dh * f).rem_euclid(360.0),
+                    lerp(s0, s1, f),
+                    lerp(l0, l1, f),
+                ])
+            }
+            ColorSpace::Lab => {
+                let (a, b) = (to_lab(c0), to_lab(c1));
+                from_lab(std::array::from_fn(|i| lerp(a[i], b[i], f)))
+            }
+        }
+    }
+}
+
+// A single white stop, matching the original black-on-white output.
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::solid(Rgb([255, 255, 255]))
+    }
+}
+
+fn invalid(line: usize, message: &str) -> MandelbrotError {
+    MandelbrotError::InvalidPalette(format!("line {line}: {message}"))
+}
+
+// The first `count` whitespace-separated numbers on `line`, of which there
+// must be at least that many.
+fn numbers(line: usize, text: &str, count: usize) -> Result<Vec<f64>, MandelbrotError> {
+    let values = text
+        .split_whitespace()
+        .take(count)
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .map_err(|_| invalid(line, "expected a number"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.len() < count {
+        return Err(invalid(line, &format!("expected {count} numbers")));
+    }
+    Ok(values)
+}
+
+fn unit_position(line: usize, position: f64) -> Result<f64, MandelbrotError> {
+    if (0.0..=1.0).contains(&position) {
+        Ok(position)
+    } else {
+        Err(invalid(line, "positions must lie in [0, 1]"))
+    }
+}
+
+fn parse_stops<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Vec<(f64, Rgb<u8>)>, MandelbrotError> {
+    lines
+        .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+        .map(|(line, text)| {
+            if text.split_whitespace().count() != 4 {
+                return Err(invalid(line, "expected `position r g b`"));
+            }
+            let values = numbers(line, text, 4)?;
+            let channel = |value: f64| {
+                if (0.0..=255.0).contains(&value) && value.fract() == 0.0 {
+                    Ok(value as u8)
+                } else {
+                    Err(invalid(line, "channels must be whole numbers in 0..=255"))
+                }
+            };
+            let color = Rgb([
+                channel(values[1])?,
+                channel(values[2])?,
+                channel(values[3])?,
+            ]);
+            Ok((unit_position(line, values[0])?, color))
+        })
+        .collect()
+}
+
+// The lines after the header: a `Name:` line, the segment count, then one
+// `left middle right r g b a r g b a` line per segment with components in
+// `[0, 1]`, optionally followed by integer blending and coloring types.
+fn parse_ggr<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Vec<(f64, Rgb<u8>)>, MandelbrotError> {
+    let mut lines = lines.filter(|(_, text)| !text.is_empty());
+    let mut next = lines.next();
+    if let Some((_, text)) = next {
+        if text.starts_with("Name:") {
+            next = lines.next();
+        }
+    }
+    let (line, count) = next.ok_or_else(|| invalid(2, "missing segment count"))?;
+    let count = count
+        .parse::<usize>()
+        .map_err(|_| invalid(line, "expected the segment count"))?;
+
+    let mut stops = Vec::with_capacity(3 * count);
+    for _ in 0..count {
+        let (line, text) = lines
+            .next()
+            .ok_or_else(|| invalid(line, "missing segments"))?;
+        let v = numbers(line, text, 11)?;
+        let (left, middle, right) = (
+            unit_position(line, v[0])?,
+            unit_position(line, v[1])?,
+            unit_position(line, v[2])?,
+        );
+        if !(left <= middle && middle <= right) {
+            return Err(invalid(line, "segment positions must be in order"));
+        }
+        if v[3..].iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return Err(invalid(line, "color components must lie in [0, 1]"));
+        }
+        let (start, end) = ([v[3], v[4], v[5]], [v[7], v[8], v[9]]);
+        let mid = std::array::from_fn(|i| (start[i] + end[i]) / 2.0);
+        stops.push((left, from_unit(start)));
+        stops.push((middle, from_unit(mid)));
+        stops.push((right, from_unit(end)));
+    }
+    Ok(stops)
+}
+
+fn lerp(a: f64, b: f64, f: f64) -> f64 {
+    a + (b - a) * f
+}
+
+fn unit(color: Rgb<u8>) -> [f64; 3] {
+    color.0.map(|c| c as f64 / 255.0)
+}
+
+fn from_unit(color: [f64; 3]) -> Rgb<u8> {
+    Rgb(channels(color).map(|c| c.round() as u8))
+}
+
+// Unit channels scaled to `0.0..=255.0`, unrounded.
+fn channels(color: [f64; 3]) -> [f64; 3] {
+    color.map(|c| c.clamp(0.0, 1.0) * 255.0)
+}
+
+// `[hue in degrees, saturation, lightness]`
+fn to_hsl(color: Rgb<u8>) -> [f64; 3] {
+    let [r, g, b] = unit(color);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return [0.0, 0.0, l];
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    [h * 60.0, s, l]
+}
+
+fn from_hsl([h, s, l]: [f64; 3]) -> [f64; 3] {
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    channels([r + m, g + m, b + m])
+}
+
+// D65 reference white in XYZ.
+const WHITE: [f64; 3] = [0.95047, 1.0, 1.08883];
+const LAB_DELTA: f64 = 6.0 / 29.0;
+
+fn to_lab(color: Rgb<u8>) -> [f64; 3] {
+    let [r, g, b] = unit(color).map(|c| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    let xyz = [
+        0.4124564 * r + 0.3575761 * g + 0.1804375 * b,
+        0.2126729 * r + 0.7151522 * g + 0.0721750 * b,
+        0.0193339 * r + 0.1191920 * g + 0.9503041 * b,
+    ];
+    let [fx, fy, fz] = std::array::from_fn(|i| {
+        let t = xyz[i] / WHITE[i];
+        if t > LAB_DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * LAB_DELTA * LAB_DELTA) + 4.0 / 29.0
+        }
+    });
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn from_lab([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let fy = (l + 16.0) / 116.0;
+    let f = [fy + a / 500.0, fy, fy - b / 200.0];
+    let [x, y, z] = std::array::from_fn(|i| {
+        let t = if f[i] > LAB_DELTA {
+            f[i].powi(3)
+        } else {
+            3.0 * LAB_DELTA * LAB_DELTA * (f[i] - 4.0 / 29.0)
+        };
+        t * WHITE[i]
+    });
+    let linear = [
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    ];
+    channels(linear.map(|c| {
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    }))
+}
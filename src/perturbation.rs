@@ -0,0 +1,173 @@
+use crate::precise::{self, from_fixed, reference_orbit, to_fixed};
+use crate::{escaped, in_main_cardioid_or_bulb, Fractal, RenderParams, SampleFn, Shader};
+use num_bigint::BigInt;
+use num_complex::Complex;
+use num_traits::Zero;
+
+// Times a reference that escapes before the iteration limit is replaced by a
+// deeper one.
+const RESEED_ATTEMPTS: usize = 3;
+// Candidates for a new reference are the pixels nearest the points of a
+// `PROBE_GRID` x `PROBE_GRID` grid spread over the image.
+const PROBE_GRID: usize = 16;
+
+// The sampler for `Precision::Perturbation`: a single orbit is iterated in
+// fixed point with `bits` fractional bits, starting from the central pixel,
+// and every sample follows only its `f64` offset from that orbit. If the
+// reference escapes early it is re-seeded at the deepest of a grid of probe
+// pixels. Unlike `Precision::Arbitrary`, supersampling is applied.
+//
+// Offsets are plain `f64`, so views narrower than about `1e-300` underflow.
+pub(crate) fn with_perturbation_shader<R>(
+    params: &RenderParams,
+    fractal: Fractal,
+    (width, height): (usize, usize),
+    bits: u32,
+    f: impl FnOnce(&Shader<&SampleFn>) -> R,
+) -> R {
+    let sampling = params.sampling;
+    let re_axis = precise::fixed_axis(params.x_min, params.x_max, width, bits, sampling);
+    let im_axis = precise::fixed_axis(params.y_min, params.y_max, height, bits, sampling);
+    let dx = sampling.spacing(params.x_min, params.x_max, width);
+    let dy = sampling.spacing(params.y_min, params.y_max, height);
+    let max_iter = params.iteration_budget();
+    let seed = |(i, j): (usize, usize)| {
+        Reference::new(fractal, &re_axis, &im_axis, (i, j), max_iter, bits)
+    };
+
+    let mut reference = seed((height / 2, width / 2));
+    for _ in 0..RESEED_ATTEMPTS {
+        let Some(depth) = reference.escape_time() else {
+            break;
+        };
+        let probes = (0..PROBE_GRID).flat_map(|a| {
+            (0..PROBE_GRID).map(move |b| {
+                let i = (2 * a + 1) * height / (2 * PROBE_GRID);
+                let j = (2 * b + 1) * width / (2 * PROBE_GRID);
+                (i, j)
+            })
+        });
+        let deepest = probes
+            .map(|(i, j)| {
+                let point =
+                    Complex::new(params.x_min + j as f64 * dx, params.y_min + i as f64 * dy);
+                let delta = Complex::new(reference.re[j], reference.im[i]);
+                let escape = reference.escape(fractal, point, delta, max_iter, params.bailout);
+                ((i, j), escape.map_or(max_iter, |(n, _)| n))
+            })
+            .max_by_key(|&(_, n)| n);
+        match deepest {
+            Some((pixel, n)) if n > depth => reference = seed(pixel),
+            _ => break,
+        }
+    }
+
+    let sample = |i: usize, j: usize, (ox, oy): (f64, f64)| {
+        let point = Complex::new(
+            params.x_min + (j as f64 + ox) * dx,
+            params.y_min + (i as f64 + oy) * dy,
+        );
+        let delta = Complex::new(reference.re[j] + ox * dx, reference.im[i] + oy * dy);
+        (
+            point,
+            reference.escape(fractal, point, delta, max_iter, params.bailout),
+        )
+    };
+    let spacing = dx.max(dy);
+    f(&Shader::new(
+        params,
+        fractal,
+        (width, height),
+        spacing,
+        params.samples_per_pixel,
+        &sample,
+    ))
+}
+
+struct Reference {
+    orbit: Vec<Complex<f64>>,
+    // The offset of every column and row from the reference pixel, exact up
+    // to the final rounding to `f64`.
+    re: Vec<f64>,
+    im: Vec<f64>,
+}
+
+impl Reference {
+    fn new(
+        fractal: Fractal,
+        re_axis: &[BigInt],
+        im_axis: &[BigInt],
+        (i, j): (usize, usize),
+        max_iter: usize,
+        bits: u32,
+    ) -> Self {
+        let (re, im) = (&re_axis[j], &im_axis[i]);
+        let orbit = match fractal {
+            Fractal::Julia(c) => reference_orbit(
+                re.clone(),
+                im.clone(),
+                &to_fixed(c.re, bits),
+                &to_fixed(c.im, bits),
+                max_iter,
+                bits,
+            ),
+            _ => reference_orbit(BigInt::zero(), BigInt::zero(), re, im, max_iter, bits),
+        };
+        let offsets = |axis: &[BigInt], origin: &BigInt| {
+            axis.iter()
+                .map(|x| from_fixed(&(x - origin), bits))
+                .collect()
+        };
+        Reference {
+            orbit,
+            re: offsets(re_axis, re),
+            im: offsets(im_axis, im),
+        }
+    }
+
+    // The iteration the reference itself escaped at, if it did.
+    fn escape_time(&self) -> Option<usize> {
+        let last = self.orbit.last()?;
+        (last.norm_sqr() > 4.0).then(|| self.orbit.len() - 2)
+    }
+
+    // The escape of the sample `delta` away from the reference, numbered like
+    // `Fractal::escape`. `point` is the same sample in absolute coordinates,
+    // only precise enough for the cardioid test.
+    //
+    // Once `|z|` drops below `|delta z|` the offset has grown too large for
+    // the reference to describe it accurately, which is what shows up as
+    // glitches; the sample then continues from the start of the reference
+    // orbit with its full `z` as the offset. Running off the end of an
+    // escaped reference is handled the same way.
+    fn escape(
+        &self,
+        fractal: Fractal,
+        point: Complex<f64>,
+        delta: Complex<f64>,
+        max_iter: usize,
+        bailout: f64,
+    ) -> Option<(usize, Complex<f64>)> {
+        let zero = Complex::new(0.0, 0.0);
+        let (mut dz, dc) = match fractal {
+            Fractal::Julia(_) => (delta, zero),
+            _ if in_main_cardioid_or_bulb(&point) => return None,
+            _ => (zero, delta),
+        };
+        let orbit = &self.orbit;
+        let mut m = 0;
+        for n in 0..max_iter {
+            dz = orbit[m] * dz * 2.0 + dz * dz + dc;
+            m += 1;
+            let z = orbit[m] + dz;
+            if escaped(z, bailout) {
+                return Some((n, z));
+            }
+            if z.norm_sqr() < dz.norm_sqr() || m == orbit.len() - 1 {
+                dz = z - orbit[0];
+                m = 0;
+            }
+        }
+        None
+    }
+}
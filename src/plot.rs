@@ -0,0 +1,143 @@
+use crate::output::{path_error, prepare_output};
+use crate::{axis_len, Fractal, MandelbrotError, Sampling};
+use image::Rgb;
+use plotters::prelude::*;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+const PLOT_MARGIN: u32 = 5;
+const PLOT_LABEL_AREA: u32 = 20;
+// Padding plotters puts around a caption, in pixels.
+const PLOT_CAPTION_PAD: u32 = 10;
+pub const PLOT_CAPTION_SIZE: u32 = 30;
+
+// Colors and caption for `plot_mandelbrot`. The plot backend has no alpha
+// channel; use `render_rgba_image` with `Exterior::Transparent` for a
+// transparent PNG. Without a caption the plot starts right at the margin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlotStyle {
+    pub interior: Rgb<u8>,
+    pub background: Rgb<u8>,
+    pub caption: Option<PlotCaption>,
+}
+
+impl Default for PlotStyle {
+    fn default() -> Self {
+        PlotStyle {
+            interior: Rgb([0, 0, 0]),
+            background: Rgb([255, 255, 255]),
+            caption: Some(PlotCaption::for_fractal(Fractal::Mandelbrot)),
+        }
+    }
+}
+
+// The title above the plot, in a font plotters can find by family name,
+// such as "sans-serif" or "serif", at `size` pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlotCaption {
+    pub text: String,
+    pub font: String,
+    pub size: u32,
+}
+
+impl PlotCaption {
+    // The name of `fractal` in the default font.
+    pub fn for_fractal(fractal: Fractal) -> Self {
+        let text = match fractal {
+            Fractal::Mandelbrot => "Mandelbrot Set",
+            Fractal::Julia(_) => "Julia Set",
+            Fractal::BurningShip => "Burning Ship",
+            Fractal::Tricorn => "Tricorn",
+            Fractal::Newton => "Newton Fractal",
+        };
+        PlotCaption {
+            text: text.to_string(),
+            font: "sans-serif".to_string(),
+            size: PLOT_CAPTION_SIZE,
+        }
+    }
+}
+
+// The image height is derived from `width` so that the plotting area has the
+// same aspect ratio as the coordinate window and the set isn't stretched.
+// `members` are points of the `complex_matrix` grid with `pixel_density`
+// over the two ranges, and each fills the rectangle of the plane closer to it
+// than to its neighbours, so the set looks solid whatever the ratio between
+// the grid and the image. Missing parent directories of `path` are created,
+// and failures to write it are `MandelbrotError::Io` naming the path.
+pub fn plot_mandelbrot(
+    members: Vec<(f64, f64)>,
+    x_range: Range<f64>,
+    y_range: Range<f64>,
+    pixel_density: i128,
+    width: u32,
+    style: &PlotStyle,
+    path: &Path,
+) -> Result<(), MandelbrotError> {
+    let [r, g, b] = style.background.0;
+    let background = RGBColor(r, g, b);
+    let [r, g, b] = style.interior.0;
+    let interior = RGBColor(r, g, b);
+
+    let spacing = |range: &Range<f64>| {
+        let samples = axis_len(range.start, range.end, pixel_density);
+        Sampling::Inclusive.spacing(range.start, range.end, samples)
+    };
+    let (dx, dy) = (spacing(&x_range) / 2.0, spacing(&y_range) / 2.0);
+
+    prepare_output(path)?;
+    let height = plot_height(&x_range, &y_range, width, style.caption.as_ref());
+    let root = BitMapBackend::new(path, (width, height)).into_drawing_area();
+    root.fill(&background).map_err(plot_error)?;
+
+    let mut builder = ChartBuilder::on(&root);
+    if let Some(caption) = &style.caption {
+        builder.caption(
+            &caption.text,
+            (caption.font.as_str(), caption.size).into_font(),
+        );
+    }
+    let mut chart = builder
+        .margin(PLOT_MARGIN)
+        .x_label_area_size(PLOT_LABEL_AREA)
+        .y_label_area_size(PLOT_LABEL_AREA)
+        .build_cartesian_2d(x_range, y_range)
+        .map_err(plot_error)?;
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .draw()
+        .map_err(plot_error)?;
+
+    chart
+        .draw_series(members.into_iter().map(|(re, im)| {
+            Rectangle::new([(re - dx, im - dy), (re + dx, im + dy)], interior.filled())
+        }))
+        .map_err(plot_error)?;
+
+    root.present()
+        .map_err(|err| path_error(path, io::Error::other(err.to_string())))?;
+    Ok(())
+}
+
+pub(crate) fn plot_error<E: std::fmt::Display>(err: E) -> MandelbrotError {
+    MandelbrotError::Plot(err.to_string())
+}
+
+// The caption's real height depends on font metrics, so it is approximated
+// by the font size plus plotters' title padding.
+fn plot_height(
+    x_range: &Range<f64>,
+    y_range: &Range<f64>,
+    width: u32,
+    caption: Option<&PlotCaption>,
+) -> u32 {
+    let chrome_x = 2 * PLOT_MARGIN + PLOT_LABEL_AREA;
+    let caption_y = caption.map_or(0, |caption| caption.size + PLOT_CAPTION_PAD);
+    let chrome_y = 2 * PLOT_MARGIN + PLOT_LABEL_AREA + caption_y;
+    let aspect = (y_range.end - y_range.start) / (x_range.end - x_range.start);
+    let area_height = (width.saturating_sub(chrome_x) as f64 * aspect).round() as u32;
+    area_height + chrome_y
+}
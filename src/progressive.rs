@@ -0,0 +1,76 @@
+use crate::{
+    save_output, validate, with_fractal_shader, with_threads, MandelbrotError, RenderParams,
+    RenderStats,
+};
+use image::{Rgb, RgbImage};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// `render` in passes that sharpen as they go: the first shades only every
+// `coarse`-th pixel of every `coarse`-th row, and each later pass halves the
+// step and shades just the pixels the earlier ones skipped, down to a step
+// of 1. After each pass `on_pass` sees the image so far, with every pixel
+// not yet shaded showing the nearest shaded one above and to its left. The
+// final image, which is also returned, is exactly the one `render` gives.
+pub fn render_progressive<F>(
+    params: &RenderParams,
+    coarse: u32,
+    mut on_pass: F,
+) -> Result<RgbImage, MandelbrotError>
+where
+    F: FnMut(&RgbImage) + Send,
+{
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    let mut image = RgbImage::new(width as u32, height as u32);
+    let mut shaded = vec![false; width * height];
+    let mut preview = image.clone();
+
+    with_threads(params.threads, || {
+        with_fractal_shader(params, params.fractal, |shader| {
+            let mut step = coarse.max(1) as usize;
+            loop {
+                // The pixels of this pass's grid that no earlier pass shaded.
+                let row = |y: usize| -> Vec<(usize, usize, [u8; 3])> {
+                    let mut stats = RenderStats::default();
+                    (0..width)
+                        .step_by(step)
+                        .filter(|&x| !shaded[y * width + x])
+                        .map(|x| {
+                            let [r, g, b, _] = shader.pixel(x, y, false, &mut stats);
+                            (x, y, [r, g, b])
+                        })
+                        .collect()
+                };
+                #[cfg(feature = "parallel")]
+                let pixels: Vec<_> = (0..height)
+                    .into_par_iter()
+                    .step_by(step)
+                    .flat_map(row)
+                    .collect();
+                #[cfg(not(feature = "parallel"))]
+                let pixels: Vec<_> = (0..height).step_by(step).flat_map(row).collect();
+                for (x, y, color) in pixels {
+                    image.put_pixel(x as u32, y as u32, Rgb(color));
+                    shaded[y * width + x] = true;
+                }
+
+                for (x, y, pixel) in preview.enumerate_pixels_mut() {
+                    let (x, y) = (x as usize, y as usize);
+                    let (ax, ay) = match shaded[y * width + x] {
+                        true => (x, y),
+                        false => (x - x % step, y - y % step),
+                    };
+                    *pixel = *image.get_pixel(ax as u32, ay as u32);
+                }
+                on_pass(&preview);
+                if step == 1 {
+                    break;
+                }
+                step /= 2;
+            }
+        })
+    })?;
+    save_output(params, &image)?;
+    Ok(image)
+}
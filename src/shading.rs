@@ -0,0 +1,71 @@
+use crate::{
+    in_main_cardioid_or_bulb, validate, with_threads, Classification, EscapeConfig, Fractal,
+    MandelbrotError, RenderParams,
+};
+use image::{Rgb, RgbImage};
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Renders the grid of `params` with every pixel colored by `shade`, which is
+// given the point's `Classification` under `params.escape_config()` and the
+// point itself, and so can compute anything else it needs, such as
+// `Fractal::smooth_escape_time`. Classifying runs in parallel; `shade` is then
+// called once per pixel, row by row from the top. As in `render_outline`,
+// the sampling is one `f64` point per pixel, and the colors are written as
+// returned, without `gamma` or the other coloring settings.
+pub fn render_with_shading<F>(
+    params: &RenderParams,
+    mut shade: F,
+) -> Result<RgbImage, MandelbrotError>
+where
+    F: FnMut(Classification, &Complex<f64>) -> Rgb<u8>,
+{
+    validate(params)?;
+    let (width, height) = params.grid_size();
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    let config = params.escape_config();
+
+    let fill = |(i, row): (usize, &mut [Classification])| {
+        for (j, fate) in row.iter_mut().enumerate() {
+            let point = params.rotated(Complex::new(re[j], im[i]));
+            *fate = classification(params.fractal, &point, &config);
+        }
+    };
+
+    let mut fates = vec![Classification::ReachedMaxIter; width * height];
+    if fates.is_empty() {
+        return Ok(RgbImage::new(width as u32, height as u32));
+    }
+    with_threads(params.threads, || {
+        #[cfg(feature = "parallel")]
+        fates.par_chunks_exact_mut(width).enumerate().for_each(fill);
+        #[cfg(not(feature = "parallel"))]
+        fates.chunks_exact_mut(width).enumerate().for_each(fill);
+    })?;
+
+    // The first row of `fates` is `y_min`, at the bottom of the image.
+    Ok(RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let (i, j) = (height - 1 - y as usize, x as usize);
+        shade(
+            fates[i * width + j],
+            &params.rotated(Complex::new(re[j], im[i])),
+        )
+    }))
+}
+
+// `classify` for any fractal. Newton's method has no cycles to detect, so
+// its points either converge or reach the limit.
+fn classification(fractal: Fractal, point: &Complex<f64>, config: &EscapeConfig) -> Classification {
+    match fractal {
+        Fractal::Mandelbrot if config.cardioid_applies() && in_main_cardioid_or_bulb(point) => {
+            Classification::InsideCardioid
+        }
+        Fractal::Newton => match fractal.escape(point, config) {
+            Some((n, _)) => Classification::Escaped(n),
+            None => Classification::ReachedMaxIter,
+        },
+        _ => fractal.fate(point, config),
+    }
+}
@@ -0,0 +1,67 @@
+use crate::plot::plot_error;
+use crate::{render, MandelbrotError, RenderParams};
+use image::{imageops, Rgb, RgbImage};
+use plotters::prelude::*;
+
+// Pixels between neighbouring thumbnails and around the sheet's edge.
+pub const SHEET_GAP: u32 = 4;
+const SHEET_BACKGROUND: Rgb<u8> = Rgb([0, 0, 0]);
+const LABEL_SIZE: u32 = 12;
+const LABEL_PAD: i32 = 2;
+
+// Renders every entry of `params_list` and tiles the images row by row,
+// `columns` to a row, into one contact sheet, each labelled in its top-left
+// corner with its position in the list, counted from 1. Cells are as large as
+// the largest render, so images of different sizes are top-left aligned in
+// theirs. Zero `columns` is an `InvalidParams` error; otherwise the first
+// error stops the sheet.
+pub fn render_grid(
+    params_list: &[RenderParams],
+    columns: usize,
+) -> Result<RgbImage, MandelbrotError> {
+    if columns == 0 {
+        return Err(MandelbrotError::InvalidParams(
+            "a contact sheet needs at least one column".into(),
+        ));
+    }
+    let images = params_list
+        .iter()
+        .map(render)
+        .collect::<Result<Vec<_>, _>>()?;
+    if images.is_empty() {
+        return Ok(RgbImage::new(0, 0));
+    }
+    let cell_width = images.iter().map(RgbImage::width).max().unwrap_or(0);
+    let cell_height = images.iter().map(RgbImage::height).max().unwrap_or(0);
+    let rows = images.len().div_ceil(columns) as u32;
+    let columns = columns.min(images.len()) as u32;
+    let mut sheet = RgbImage::from_pixel(
+        columns * (cell_width + SHEET_GAP) + SHEET_GAP,
+        rows * (cell_height + SHEET_GAP) + SHEET_GAP,
+        SHEET_BACKGROUND,
+    );
+    let origin = |k: usize| {
+        let (column, row) = (k as u32 % columns, k as u32 / columns);
+        (
+            SHEET_GAP + column * (cell_width + SHEET_GAP),
+            SHEET_GAP + row * (cell_height + SHEET_GAP),
+        )
+    };
+    for (k, image) in images.iter().enumerate() {
+        let (x, y) = origin(k);
+        imageops::replace(&mut sheet, image, x as i64, y as i64);
+    }
+
+    let size = sheet.dimensions();
+    let root = BitMapBackend::with_buffer(&mut sheet, size).into_drawing_area();
+    let font = ("sans-serif", LABEL_SIZE).into_font().color(&WHITE);
+    for k in 0..images.len() {
+        let (x, y) = origin(k);
+        let position = (x as i32 + LABEL_PAD, y as i32 + LABEL_PAD);
+        root.draw(&Text::new((k + 1).to_string(), position, &font))
+            .map_err(plot_error)?;
+    }
+    root.present().map_err(plot_error)?;
+    drop(root);
+    Ok(sheet)
+}
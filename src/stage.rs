@@ -0,0 +1,148 @@
+use crate::{gamma_table, render, MandelbrotError, RenderParams, BAYER};
+use image::{Rgb, RgbImage};
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Where the pixel a `PixelStage` is coloring sits: its column and row from
+// the top-left corner of a `width` x `height` image, and the point of the
+// plane sampled there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelContext {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub point: Complex<f64>,
+}
+
+// One step of a post-processing chain, run on every finished pixel by
+// `apply_stages`. Stages see the output of the one before.
+pub trait PixelStage: Send + Sync {
+    fn apply(&self, color: Rgb<u8>, ctx: &PixelContext) -> Rgb<u8>;
+}
+
+// The same curve as `RenderParams::gamma`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gamma {
+    table: Option<[u8; 256]>,
+}
+
+impl Gamma {
+    // Fails for the gammas `RenderParams::gamma` refuses: anything not
+    // positive and finite.
+    pub fn new(gamma: f64) -> Result<Self, MandelbrotError> {
+        if !(gamma > 0.0 && gamma.is_finite()) {
+            return Err(MandelbrotError::InvalidGamma(gamma));
+        }
+        Ok(Gamma {
+            table: gamma_table(gamma),
+        })
+    }
+}
+
+impl PixelStage for Gamma {
+    fn apply(&self, color: Rgb<u8>, _: &PixelContext) -> Rgb<u8> {
+        match &self.table {
+            Some(table) => Rgb(color.0.map(|c| table[c as usize])),
+            None => color,
+        }
+    }
+}
+
+// Cuts each channel down to `levels` evenly spaced values, from 0 to 255,
+// choosing between the two nearest by the 4 x 4 Bayer matrix of
+// `Dither::Ordered` so smooth gradients become patterns rather than bands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderedDither {
+    levels: u8,
+}
+
+impl OrderedDither {
+    // Fails for fewer than 2 levels, which leave nothing to choose between.
+    pub fn new(levels: u8) -> Result<Self, MandelbrotError> {
+        if levels < 2 {
+            let message = format!("dithering needs at least 2 levels, got {levels}");
+            return Err(MandelbrotError::InvalidParams(message));
+        }
+        Ok(OrderedDither { levels })
+    }
+}
+
+impl PixelStage for OrderedDither {
+    fn apply(&self, color: Rgb<u8>, ctx: &PixelContext) -> Rgb<u8> {
+        let steps = (self.levels - 1) as f64;
+        let threshold = (BAYER[ctx.y as usize % 4][ctx.x as usize % 4] as f64 + 0.5) / 16.0;
+        Rgb(color.0.map(|c| {
+            let scaled = c as f64 / 255.0 * steps;
+            let level = scaled.floor() + (scaled.fract() > threshold) as u8 as f64;
+            (level / steps * 255.0).round() as u8
+        }))
+    }
+}
+
+// Blends every pixel `amount` of the way towards `color`: 0 leaves it alone
+// and 1 replaces it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tint {
+    pub color: Rgb<u8>,
+    pub amount: f64,
+}
+
+impl PixelStage for Tint {
+    fn apply(&self, color: Rgb<u8>, _: &PixelContext) -> Rgb<u8> {
+        let mut out = color;
+        for (c, &target) in out.0.iter_mut().zip(&self.color.0) {
+            let blended = *c as f64 + (target as f64 - *c as f64) * self.amount;
+            *c = blended.round().clamp(0.0, 255.0) as u8;
+        }
+        out
+    }
+}
+
+// Runs `stages`, in order, over every pixel of `image`, which must be the
+// image `render` produced for `params` so that each pixel's point is the one
+// sampled there.
+pub fn apply_stages(image: &mut RgbImage, params: &RenderParams, stages: &[Box<dyn PixelStage>]) {
+    let (width, height) = image.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    if stages.is_empty() || w == 0 || h == 0 {
+        return;
+    }
+    let re = params.sampling.axis(params.x_min, params.x_max, w);
+    let im = params.sampling.axis(params.y_min, params.y_max, h);
+    let shade = |(y, row): (usize, &mut [u8])| {
+        for (x, pixel) in row.chunks_exact_mut(3).enumerate() {
+            let ctx = PixelContext {
+                x: x as u32,
+                y: y as u32,
+                width,
+                height,
+                point: params.rotated(Complex::new(re[x], im[h - 1 - y])),
+            };
+            let color = Rgb([pixel[0], pixel[1], pixel[2]]);
+            let color = stages
+                .iter()
+                .fold(color, |color, stage| stage.apply(color, &ctx));
+            pixel.copy_from_slice(&color.0);
+        }
+    };
+    let buffer: &mut [u8] = image;
+    #[cfg(feature = "parallel")]
+    buffer
+        .par_chunks_exact_mut(w * 3)
+        .enumerate()
+        .for_each(shade);
+    #[cfg(not(feature = "parallel"))]
+    buffer.chunks_exact_mut(w * 3).enumerate().for_each(shade);
+}
+
+// `render` followed by `apply_stages`.
+pub fn render_with_stages(
+    params: &RenderParams,
+    stages: &[Box<dyn PixelStage>],
+) -> Result<RgbImage, MandelbrotError> {
+    let mut image = render(params)?;
+    apply_stages(&mut image, params, stages);
+    Ok(image)
+}
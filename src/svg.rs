@@ -0,0 +1,121 @@
+use crate::outline::membership;
+use crate::{MandelbrotError, RenderParams};
+use ndarray::Array2;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// The outline of a `membership_grid` as closed polylines, found by marching
+// squares: each crossing between a member and a non-member neighbour gets a
+// vertex halfway between them. Points are `(column, row)` in grid units, and
+// each loop repeats its first point at the end. Cells beyond the grid count
+// as non-members, so a set cut off by the window edge is closed along it.
+// Diagonal members that only touch at a corner get separate loops, as in
+// `boundary_grid`. Runs of collinear vertices are merged.
+pub fn trace_boundary(grid: &Array2<bool>) -> Vec<Vec<(f64, f64)>> {
+    let (height, width) = grid.dim();
+    let (height, width) = (height as i64, width as i64);
+    let member = |r: i64, c: i64| {
+        (0..height).contains(&r) && (0..width).contains(&c) && grid[[r as usize, c as usize]]
+    };
+
+    // Vertices are kept at twice their grid coordinates, `(2 * row, 2 *
+    // column)`, which makes every edge midpoint whole. Segments run with the
+    // set on the same side, so each vertex starts exactly one of them.
+    let mut next = HashMap::new();
+    for r in -1..height {
+        for c in -1..width {
+            let corners = [(r, c), (r, c + 1), (r + 1, c + 1), (r + 1, c)];
+            let edges = [
+                (2 * r, 2 * c + 1),
+                (2 * r + 1, 2 * c + 2),
+                (2 * r + 2, 2 * c + 1),
+                (2 * r + 1, 2 * c),
+            ];
+            let inside = corners.map(|(r, c)| member(r, c));
+            // Crossings in order around the cell, as `(edge, leaving the set)`.
+            let crossings = (0..4)
+                .filter(|&k| inside[k] != inside[(k + 1) % 4])
+                .map(|k| (edges[k], inside[k]))
+                .collect::<Vec<_>>();
+            let n = crossings.len();
+            for (k, &(from, leaving)) in crossings.iter().enumerate() {
+                if leaving {
+                    // The crossing before, into the set, closes off the
+                    // member corners between the two.
+                    next.insert(from, crossings[(k + n - 1) % n].0);
+                }
+            }
+        }
+    }
+
+    let mut starts = next.keys().copied().collect::<Vec<_>>();
+    starts.sort_unstable();
+    let mut loops = Vec::new();
+    for start in starts {
+        if !next.contains_key(&start) {
+            continue;
+        }
+        let mut vertices = vec![start];
+        let mut at = start;
+        while let Some(to) = next.remove(&at) {
+            vertices.push(to);
+            at = to;
+        }
+        loops.push(simplify(&vertices));
+    }
+    loops
+}
+
+// `vertices`, a closed loop of doubled coordinates, without the points in
+// the middle of straight runs, as `(column, row)`.
+fn simplify(vertices: &[(i64, i64)]) -> Vec<(f64, f64)> {
+    let n = vertices.len() - 1;
+    let straight = |k: usize| {
+        let (a, b, c) = (
+            vertices[(k + n - 1) % n],
+            vertices[k],
+            vertices[(k + 1) % n],
+        );
+        (b.0 - a.0) * (c.1 - b.1) == (b.1 - a.1) * (c.0 - b.0)
+    };
+    let mut kept = (0..n)
+        .filter(|&k| !straight(k))
+        .map(|k| (vertices[k].1 as f64 / 2.0, vertices[k].0 as f64 / 2.0))
+        .collect::<Vec<_>>();
+    kept.push(kept[0]);
+    kept
+}
+
+// The `trace_boundary` of the window in `params` as an SVG document the
+// pixel size `render` would use, with the loops stroked one unit wide in
+// `params.interior` and left unfilled. Grid point `(column, row)` sits at
+// the centre of its pixel, so the paths line up with `render_outline`. The
+// sampling is one `f64` point per pixel and coloring settings are ignored.
+pub fn render_svg(params: &RenderParams) -> Result<String, MandelbrotError> {
+    let members = membership(params)?;
+    let (height, width) = members.dim();
+    let mut path = String::new();
+    for vertices in trace_boundary(&members) {
+        for (k, &(column, row)) in vertices.iter().enumerate() {
+            let command = if k == 0 { 'M' } else { 'L' };
+            let (x, y) = (column + 0.5, height as f64 - 0.5 - row);
+            write!(path, "{command}{x} {y} ").expect("writing to a String");
+        }
+        path.push_str("Z ");
+    }
+    let [r, g, b] = params.interior.0;
+    Ok(format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" "#,
+            "viewBox=\"0 0 {width} {height}\">\n",
+            r##"<path d="{path}" fill="none" stroke="#{r:02x}{g:02x}{b:02x}" stroke-width="1"/>"##,
+            "\n</svg>\n",
+        ),
+        width = width,
+        height = height,
+        path = path.trim_end(),
+        r = r,
+        g = g,
+        b = b,
+    ))
+}
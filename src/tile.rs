@@ -0,0 +1,73 @@
+use crate::{shade_samples, validate, with_threads, MandelbrotError, RenderParams};
+use image::RgbImage;
+use num_complex::Complex;
+
+// The square `(x_min, x_max, y_min, y_max)` covered by the single tile at
+// zoom 0. Zoom `z` splits it into a `2^z` x `2^z` grid, with tile `(0, 0)` in
+// the top-left corner as in slippy maps.
+pub const TILE_BOUNDS: (f64, f64, f64, f64) = (-2.25, 0.75, -1.5, 1.5);
+
+pub fn tile_bounds(zoom: u32, tile_x: u32, tile_y: u32) -> (f64, f64, f64, f64) {
+    let (x_min, x_max, _, y_max) = TILE_BOUNDS;
+    let span = (x_max - x_min) / 2f64.powi(zoom as i32);
+    let left = x_min + tile_x as f64 * span;
+    let top = y_max - tile_y as f64 * span;
+    (left, left + span, top - span, top)
+}
+
+// Renders one `tile_size` x `tile_size` tile with the coloring, fractal and
+// iteration settings from `params` (its bounds and density are ignored, and
+// `IterationPolicy::AutoZoom` scales with the tile's own zoom).
+// Pixels sample the centres of their cells whatever `params.sampling` says,
+// so no sample lies on a tile edge and neighbouring tiles line up without
+// duplicated rows or columns. The rest is checked as `render` would, with
+// `tile_size` as the image size.
+pub fn render_tile(
+    params: &RenderParams,
+    zoom: u32,
+    tile_x: u32,
+    tile_y: u32,
+    tile_size: u32,
+) -> Result<RgbImage, MandelbrotError> {
+    let tiles = 1u64.checked_shl(zoom).unwrap_or(0);
+    if tiles == 0 || tile_x as u64 >= tiles || tile_y as u64 >= tiles {
+        return Err(MandelbrotError::InvalidTile {
+            zoom,
+            tile_x,
+            tile_y,
+        });
+    }
+
+    let (x_min, x_max, y_min, y_max) = tile_bounds(zoom, tile_x, tile_y);
+    let params = &RenderParams {
+        x_min,
+        x_max,
+        y_min,
+        y_max,
+        size: Some((tile_size, tile_size)),
+        ..params.clone()
+    };
+    validate(params)?;
+    let step = (x_max - x_min) / tile_size as f64;
+    let size = tile_size as usize;
+    let config = params.escape_config();
+    let sample = |i: usize, j: usize, (ox, oy): (f64, f64)| {
+        let point = Complex::new(
+            x_min + (j as f64 + 0.5 + ox) * step,
+            y_min + (i as f64 + 0.5 + oy) * step,
+        );
+        (point, params.fractal.escape(&point, &config))
+    };
+
+    with_threads(params.threads, || {
+        shade_samples(
+            params,
+            params.fractal,
+            (size, size),
+            step,
+            params.samples_per_pixel,
+            sample,
+            &mut |_, _| {},
+        )
+    })
+}
@@ -0,0 +1,72 @@
+use crate::{
+    escaped, validate, with_fractal_shader, with_threads, Fractal, IterationPolicy,
+    MandelbrotError, Precision, RenderParams, Viewport,
+};
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Renders the Mandelbrot set over `viewport` the way `render` does, with
+// every `f64` shortcut it would take there (the cardioid and bulb test,
+// periodicity detection, the mirrored half and the vector path), and checks
+// each pixel's membership against a brute-force loop of `max_iter` steps of
+// `z^2 + c` that only stops on escaping. The first pixel, in reading order,
+// where the two disagree is returned as `MandelbrotError::VerificationFailed`.
+//
+// Both sides sample in `f64`, so views deep enough for `Precision::Auto` to
+// leave it still only check the `f64` paths.
+pub fn verify_grid(viewport: &Viewport, max_iter: usize) -> Result<(), MandelbrotError> {
+    let params = RenderParams {
+        iterations: IterationPolicy::Fixed(max_iter),
+        precision: Precision::Double,
+        ..RenderParams::default()
+    }
+    .with_viewport(viewport);
+    validate(&params)?;
+    let (width, height) = params.grid_size();
+    let re = params.sampling.axis(params.x_min, params.x_max, width);
+    let im = params.sampling.axis(params.y_min, params.y_max, height);
+    let bailout = params.escape_config().bailout_sqr;
+
+    let mismatch = with_threads(params.threads, || {
+        with_fractal_shader(&params, Fractal::Mandelbrot, |shader| {
+            let check = |y: usize| {
+                let source = shader.mirror_of(y).unwrap_or(y);
+                (0..width).find_map(|x| {
+                    let (_, escape) = (shader.sample)(height - 1 - source, x, (0.0, 0.0));
+                    let point = params.rotated(Complex::new(re[x], im[height - 1 - y]));
+                    let member = escape.is_none();
+                    (member != brute_force_member(point, max_iter, bailout))
+                        .then_some((x as u32, y as u32, point, member))
+                })
+            };
+            #[cfg(feature = "parallel")]
+            return (0..height)
+                .into_par_iter()
+                .filter_map(check)
+                .min_by_key(|m| m.1);
+            #[cfg(not(feature = "parallel"))]
+            (0..height).find_map(check)
+        })
+    })?;
+    match mismatch {
+        Some((x, y, point, member)) => Err(MandelbrotError::VerificationFailed {
+            x,
+            y,
+            point,
+            member,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn brute_force_member(c: Complex<f64>, max_iter: usize, bailout: f64) -> bool {
+    let mut z = Complex::new(0.0, 0.0);
+    for _ in 0..max_iter {
+        z = z * z + c;
+        if escaped(z, bailout) {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,328 @@
+use crate::{escape_counts, Fractal, IterationPolicy, RenderParams, Sampling};
+use ndarray::Array2;
+use num_complex::Complex;
+
+// The height of the complex-plane window at zoom 1, enough to show the whole
+// Mandelbrot set vertically.
+pub const ZOOM_1_HEIGHT: f64 = 3.0;
+
+// A window described the way people zoom: where to look, how far in, and the
+// pixel size of the picture. The horizontal span always follows from the
+// pixel aspect ratio, so pixels stay square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Viewport {
+    pub center: Complex<f64>,
+    pub zoom: f64,
+    pub width: u32,
+    pub height: u32,
+    // Radians the window is turned counterclockwise about `center`; see
+    // `RenderParams::rotation`.
+    pub rotation: f64,
+}
+
+impl Viewport {
+    // An upright window.
+    pub fn new(center: Complex<f64>, zoom: f64, width: u32, height: u32) -> Self {
+        Viewport {
+            center,
+            zoom,
+            width,
+            height,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_rotation(self, rotation: f64) -> Self {
+        Viewport { rotation, ..self }
+    }
+
+    // `(x_min, x_max, y_min, y_max)`, before `rotation` is applied.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        let half_height = ZOOM_1_HEIGHT / self.zoom / 2.0;
+        let half_width = half_height * self.width as f64 / self.height as f64;
+        (
+            self.center.re - half_width,
+            self.center.re + half_width,
+            self.center.im - half_height,
+            self.center.im + half_height,
+        )
+    }
+
+    // The part of this view between fractions `x0..x1` of its width and
+    // `y0..y1` of its height, measured from the top-left corner as in the
+    // image. The pixel size scales with the fractions, so the density stays
+    // the same and zooming in repeatedly never stretches pixels. The whole
+    // view `(0, 0, 1, 1)` comes back unchanged; across other fractions the
+    // bounds match the parent's to within rounding of the pixel size.
+    pub fn subregion(&self, x0: f64, y0: f64, x1: f64, y1: f64) -> Viewport {
+        assert!(
+            (0.0..x1).contains(&x0) && x1 <= 1.0 && (0.0..y1).contains(&y0) && y1 <= 1.0,
+            "subregion fractions must satisfy 0 <= x0 < x1 <= 1 and 0 <= y0 < y1 <= 1"
+        );
+        if (x0, y0, x1, y1) == (0.0, 0.0, 1.0, 1.0) {
+            return *self;
+        }
+        let (x_min, x_max, y_min, y_max) = self.bounds();
+        let center = Complex::new(
+            x_min + (x0 + x1) / 2.0 * (x_max - x_min),
+            y_max - (y0 + y1) / 2.0 * (y_max - y_min),
+        );
+        let width = ((x1 - x0) * self.width as f64).round().max(1.0) as u32;
+        let height = ((y1 - y0) * self.height as f64).round().max(1.0) as u32;
+        let center = rotate(center, self.bounds(), self.rotation);
+        Viewport::new(center, self.zoom / (y1 - y0), width, height).with_rotation(self.rotation)
+    }
+
+    // `pixel_to_complex` for this view, rotation included, so a click at
+    // `(px, py)` finds the point drawn there.
+    pub fn pixel_to_complex(&self, px: f64, py: f64) -> Complex<f64> {
+        let size = (self.width as usize, self.height as usize);
+        let point = pixel_to_complex(px, py, self.bounds(), size.0, size.1);
+        rotate(point, self.bounds(), self.rotation)
+    }
+
+    // The inverse of `Viewport::pixel_to_complex`.
+    pub fn complex_to_pixel(&self, c: Complex<f64>) -> (f64, f64) {
+        let size = (self.width as usize, self.height as usize);
+        let upright = rotate(c, self.bounds(), -self.rotation);
+        complex_to_pixel(upright, self.bounds(), size.0, size.1)
+    }
+}
+
+// A reasonable iteration limit for `viewport`, the one
+// `IterationPolicy::Suggested` picks: grows by `SUGGESTED_BASE_ITER` for
+// every doubling of the zoom and stops at `MAX_SUGGESTED_ITER`.
+pub fn suggest_max_iter(viewport: &Viewport) -> usize {
+    IterationPolicy::Suggested.max_iter(viewport.zoom)
+}
+
+// How the motion between two keyframes speeds up and slows down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    // A steady rate from one keyframe to the next.
+    #[default]
+    Linear,
+    // Smoothstep: starts and ends at rest, fastest halfway.
+    EaseInOut,
+}
+
+impl Easing {
+    // Progress through a transition at time `t` in `0..=1`, also in `0..=1`.
+    pub fn apply(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// A view an animation passes through, held for `hold_frames` frames beyond
+// the one it first appears in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub viewport: Viewport,
+    pub hold_frames: usize,
+}
+
+// Every frame of an animation through `keyframes`: each keyframe's viewport
+// `1 + hold_frames` times, then `transition_frames` in-between views leading
+// to the next one, not counting either end. Between keyframes the zoom
+// changes geometrically and the centre moves in proportion to the current
+// span, so the motion looks the same speed on screen at every depth, with
+// `easing` applied to the progress; rotation turns linearly in the eased
+// progress. In-between views keep the pixel size of the keyframe they leave.
+pub fn keyframe_viewports(
+    keyframes: &[Keyframe],
+    transition_frames: usize,
+    easing: Easing,
+) -> Vec<Viewport> {
+    let mut viewports = Vec::new();
+    for (k, keyframe) in keyframes.iter().enumerate() {
+        viewports.extend(std::iter::repeat_n(
+            keyframe.viewport,
+            1 + keyframe.hold_frames,
+        ));
+        let Some(next) = keyframes.get(k + 1) else {
+            break;
+        };
+        let (from, to) = (&keyframe.viewport, &next.viewport);
+        for frame in 1..=transition_frames {
+            let t = easing.apply(frame as f64 / (transition_frames + 1) as f64);
+            viewports.push(between(from, to, t));
+        }
+    }
+    viewports
+}
+
+// The view a fraction `t` of the way from `from` to `to`. The span shrinks
+// by `r = from.zoom / to.zoom` over the transition; moving the centre at a
+// rate proportional to the span gives the fraction `(1 - r^t) / (1 - r)` of
+// the way, which is `t` when the zoom doesn't change.
+fn between(from: &Viewport, to: &Viewport, t: f64) -> Viewport {
+    let ratio = from.zoom / to.zoom;
+    let zoom = from.zoom * (to.zoom / from.zoom).powf(t);
+    let travel = if (ratio - 1.0).abs() > 1e-12 {
+        (1.0 - ratio.powf(t)) / (1.0 - ratio)
+    } else {
+        t
+    };
+    let center = from.center + (to.center - from.center) * travel;
+    let rotation = from.rotation + (to.rotation - from.rotation) * t;
+    Viewport::new(center, zoom, from.width, from.height).with_rotation(rotation)
+}
+
+impl RenderParams {
+    // Takes the bounds and exact pixel size from `viewport`, leaving the rest
+    // of the settings alone.
+    pub fn with_viewport(self, viewport: &Viewport) -> Self {
+        let (x_min, x_max, y_min, y_max) = viewport.bounds();
+        RenderParams {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            size: Some((viewport.width, viewport.height)),
+            rotation: viewport.rotation,
+            ..self
+        }
+    }
+
+    // The point sampled for grid point `upright` once `rotation` is applied.
+    pub(crate) fn rotated(&self, upright: Complex<f64>) -> Complex<f64> {
+        rotate(
+            upright,
+            (self.x_min, self.x_max, self.y_min, self.y_max),
+            self.rotation,
+        )
+    }
+
+    // The inverse of `rotated`: the grid point at which `point` is sampled.
+    pub(crate) fn upright(&self, point: Complex<f64>) -> Complex<f64> {
+        rotate(
+            point,
+            (self.x_min, self.x_max, self.y_min, self.y_max),
+            -self.rotation,
+        )
+    }
+}
+
+// `point` turned `angle` radians counterclockwise about the centre of
+// `bounds`, `(x_min, x_max, y_min, y_max)`. An angle of 0 returns `point`
+// exactly.
+fn rotate(point: Complex<f64>, bounds: (f64, f64, f64, f64), angle: f64) -> Complex<f64> {
+    if angle == 0.0 {
+        return point;
+    }
+    let (x_min, x_max, y_min, y_max) = bounds;
+    let center = Complex::new((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+    center + (point - center) * Complex::from_polar(1.0, angle)
+}
+
+// The point of the plane at pixel `(px, py)` of a `width` x `height` image
+// of `viewport`, `(x_min, x_max, y_min, y_max)`, measured from the top-left
+// corner as in the image. Whole coordinates land exactly on the points
+// `complex_matrix` samples, with `py = 0` on the row at `y_max`; fractional
+// ones, such as a mouse position, fall between them.
+pub fn pixel_to_complex(
+    px: f64,
+    py: f64,
+    viewport: (f64, f64, f64, f64),
+    width: usize,
+    height: usize,
+) -> Complex<f64> {
+    let (x_min, x_max, y_min, y_max) = viewport;
+    let dx = Sampling::Inclusive.spacing(x_min, x_max, width);
+    let dy = Sampling::Inclusive.spacing(y_min, y_max, height);
+    let row = height.saturating_sub(1) as f64 - py;
+    Complex::new(x_min + px * dx, y_min + row * dy)
+}
+
+// The inverse of `pixel_to_complex`: where `c` falls in the image, in
+// fractional pixels. Rounding gives the pixel whose sample is nearest, so a
+// pixel's own point comes back as that pixel. An axis with a single pixel
+// maps everything to 0.
+pub fn complex_to_pixel(
+    c: Complex<f64>,
+    viewport: (f64, f64, f64, f64),
+    width: usize,
+    height: usize,
+) -> (f64, f64) {
+    let (x_min, x_max, y_min, y_max) = viewport;
+    let dx = Sampling::Inclusive.spacing(x_min, x_max, width);
+    let dy = Sampling::Inclusive.spacing(y_min, y_max, height);
+    let px = if dx > 0.0 { (c.re - x_min) / dx } else { 0.0 };
+    let row = if dy > 0.0 { (c.im - y_min) / dy } else { 0.0 };
+    (px, height.saturating_sub(1) as f64 - row)
+}
+
+// The real and imaginary coordinates of the columns and rows of a `width` x
+// `height` image of `viewport`, `(x_min, x_max, y_min, y_max)`, exactly as
+// `complex_matrix` samples them: both run upward from the minimum, so the
+// imaginary one lists image rows bottom to top.
+pub fn axis_coordinates(
+    viewport: (f64, f64, f64, f64),
+    width: usize,
+    height: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let (x_min, x_max, y_min, y_max) = viewport;
+    (
+        Sampling::Inclusive.axis(x_min, x_max, width),
+        Sampling::Inclusive.axis(y_min, y_max, height),
+    )
+}
+
+// Half the side of the square of pixels `find_interesting_point` compares
+// each pixel with.
+const NEIGHBOURHOOD: usize = 2;
+
+// A pixel of `viewport` worth zooming into next, as its point, for exploring
+// the Mandelbrot set automatically: of the escaped pixels with a member
+// among their neighbours, the one whose escaped neighbours' counts vary the
+// most, which is where the picture shows the most detail. The neighbours
+// are the pixels up to `NEIGHBOURHOOD` away along both axes. Without any
+// pixel by the set, all of the escaped ones compete; ties, and views with
+// nothing escaped, go to the pixel nearest the centre. Zooming in on the
+// result over and over gives an endless dive.
+pub fn find_interesting_point(viewport: &Viewport, max_iter: usize) -> Complex<f64> {
+    let (width, height) = (viewport.width as usize, viewport.height as usize);
+    let points = Array2::from_shape_fn((height, width), |(y, x)| {
+        viewport.pixel_to_complex(x as f64, y as f64)
+    });
+    let counts = escape_counts(&points, max_iter, Fractal::Mandelbrot);
+    let member = |y: usize, x: usize| counts[[y, x]] as usize >= max_iter;
+
+    let mut best = None;
+    for ((y, x), point) in points.indexed_iter() {
+        if member(y, x) {
+            continue;
+        }
+        let ys = y.saturating_sub(NEIGHBOURHOOD)..(y + NEIGHBOURHOOD + 1).min(height);
+        let xs = x.saturating_sub(NEIGHBOURHOOD)..(x + NEIGHBOURHOOD + 1).min(width);
+        let mut near_set = false;
+        let mut escaped = Vec::new();
+        for (ny, nx) in ys.flat_map(|ny| xs.clone().map(move |nx| (ny, nx))) {
+            if member(ny, nx) {
+                near_set = true;
+            } else {
+                escaped.push(counts[[ny, nx]] as f64);
+            }
+        }
+        let mean = escaped.iter().sum::<f64>() / escaped.len() as f64;
+        let variance =
+            escaped.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / escaped.len() as f64;
+        let distance = (point - viewport.center).norm_sqr();
+        let better = match best {
+            None => true,
+            Some((best_near, best_variance, best_distance, _)) => {
+                (near_set, variance) > (best_near, best_variance)
+                    || ((near_set, variance) == (best_near, best_variance)
+                        && distance < best_distance)
+            }
+        };
+        if better {
+            best = Some((near_set, variance, distance, *point));
+        }
+    }
+    best.map_or(viewport.center, |(_, _, _, point)| point)
+}
@@ -0,0 +1,228 @@
+use crate::{
+    keyframe_viewports, render, render_path, save_as, shade_samples, validate, with_threads,
+    Easing, Keyframe, MandelbrotError, OutputFormat, RenderParams, RenderPath, Viewport,
+};
+use num_complex::Complex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::hash_map::{HashMap, RandomState};
+use std::fs;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// The cache of `render_zoom_sequence_cached` is split into this many maps,
+// each behind its own lock, so parallel rows rarely wait on each other.
+const CACHE_SHARDS: usize = 64;
+
+// A snapped sample point by the bits of its coordinates, together with the
+// iteration limit its escape was computed under.
+type CacheKey = (u64, u64, usize);
+type Escape = Option<(usize, Complex<f64>)>;
+
+// Renders `frames` PNGs named `frame_0000.png`, `frame_0001.png`, ... into
+// `out_dir`, all centred on `target`. The zoom grows geometrically from
+// `start_zoom` to `end_zoom`, so each frame magnifies the previous one by the
+// same factor and the dive looks steady when played back. Frames have the
+// pixel size `params` would render and share its other settings, except that
+// `params.output` is not saved.
+//
+// With the `parallel` feature the frames render concurrently, each on its own
+// task, and within each frame the rows are spread over whatever workers are
+// idle, all on one pool of `params.threads` workers so the total stays within
+// that budget. Every frame writes only its own file, in whatever order they
+// finish; the first error is returned.
+pub fn render_zoom_sequence(
+    params: &RenderParams,
+    target: Complex<f64>,
+    start_zoom: f64,
+    end_zoom: f64,
+    frames: usize,
+    out_dir: &Path,
+) -> Result<(), MandelbrotError> {
+    let (width, height) = params.grid_size();
+    let viewports = (0..frames)
+        .map(|frame| {
+            let zoom = frame_zoom(start_zoom, end_zoom, frame, frames);
+            Viewport::new(target, zoom, width as u32, height as u32)
+        })
+        .collect::<Vec<_>>();
+    render_frames(params, &viewports, out_dir)
+}
+
+// Renders the animation `keyframe_viewports` lays out into `out_dir`, named
+// and rendered like `render_zoom_sequence`'s frames but with each frame's
+// pixel size taken from its viewport. Returns the number of frames.
+pub fn render_keyframes(
+    params: &RenderParams,
+    keyframes: &[Keyframe],
+    transition_frames: usize,
+    easing: Easing,
+    out_dir: &Path,
+) -> Result<usize, MandelbrotError> {
+    let viewports = keyframe_viewports(keyframes, transition_frames, easing);
+    render_frames(params, &viewports, out_dir)?;
+    Ok(viewports.len())
+}
+
+// Saves `params` rendered at each of `viewports` as `frame_NNNN.png` in
+// `out_dir`, concurrently as `render_zoom_sequence` describes.
+fn render_frames(
+    params: &RenderParams,
+    viewports: &[Viewport],
+    out_dir: &Path,
+) -> Result<(), MandelbrotError> {
+    fs::create_dir_all(out_dir)?;
+    let render_frame = |(frame, viewport): (usize, &Viewport)| {
+        // `None` shares the pool the frames already run on.
+        let frame_params = RenderParams {
+            threads: None,
+            output: None,
+            ..params.clone().with_viewport(viewport)
+        };
+        let image = render(&frame_params)?;
+        let path = out_dir.join(format!("frame_{frame:04}.png"));
+        save_as(&image, &path, OutputFormat::Png)
+    };
+    with_threads(params.threads, || {
+        #[cfg(feature = "parallel")]
+        return viewports.par_iter().enumerate().try_for_each(render_frame);
+        #[cfg(not(feature = "parallel"))]
+        viewports.iter().enumerate().try_for_each(render_frame)
+    })?
+}
+
+fn frame_zoom(start_zoom: f64, end_zoom: f64, frame: usize, frames: usize) -> f64 {
+    if frames < 2 {
+        return start_zoom;
+    }
+    let t = frame as f64 / (frames - 1) as f64;
+    start_zoom * (end_zoom / start_zoom).powf(t)
+}
+
+// How much work the escape cache of `render_zoom_sequence_cached` saved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZoomCacheStats {
+    pub lookups: u64,
+    // Samples whose escape was found in the cache instead of iterated.
+    pub hits: u64,
+}
+
+// A zoom laid out and saved like `render_zoom_sequence`'s, but with
+// successive frames reusing each other's escapes. These are not the frames
+// `render_zoom_sequence` saves: so that the samples of nearby zooms coincide
+// exactly, each frame snaps its sample points to the power-of-two lattice
+// between a quarter and half of its pixel spacing, moving them by up to a
+// quarter of a pixel from where `render` would sample, and pixels near the
+// boundary can come out differently. The cache itself changes nothing: a
+// frame is bit-for-bit what iterating each of its snapped samples gives.
+// Only the previous frame's escapes are kept, so memory stays at about two
+// frames' worth. Frames render one after another, each spread over the
+// workers as usual. A frame too deep for `f64`, for which `render_path` is
+// neither `Scalar` nor `Simd`, is rendered by `render` itself, uncached and
+// unsnapped, at the precision `params` asks for. The saving grows as the
+// zoom between frames shrinks.
+pub fn render_zoom_sequence_cached(
+    params: &RenderParams,
+    target: Complex<f64>,
+    start_zoom: f64,
+    end_zoom: f64,
+    frames: usize,
+    out_dir: &Path,
+) -> Result<ZoomCacheStats, MandelbrotError> {
+    let (width, height) = params.grid_size();
+    fs::create_dir_all(out_dir)?;
+    let hasher = RandomState::new();
+    let shard = |key: &CacheKey| hasher.hash_one(key) as usize % CACHE_SHARDS;
+    let mut previous = vec![HashMap::new(); CACHE_SHARDS];
+    let mut stats = ZoomCacheStats::default();
+    for frame in 0..frames {
+        let zoom = frame_zoom(start_zoom, end_zoom, frame, frames);
+        let viewport = Viewport::new(target, zoom, width as u32, height as u32);
+        let frame_params = &RenderParams {
+            output: None,
+            ..params.clone().with_viewport(&viewport)
+        };
+        validate(frame_params)?;
+        let path = out_dir.join(format!("frame_{frame:04}.png"));
+        if !matches!(
+            render_path(frame_params),
+            RenderPath::Scalar | RenderPath::Simd
+        ) {
+            save_as(&render(frame_params)?, &path, OutputFormat::Png)?;
+            previous = vec![HashMap::new(); CACHE_SHARDS];
+            continue;
+        }
+
+        let sampling = frame_params.sampling;
+        let (x_min, x_max) = (frame_params.x_min, frame_params.x_max);
+        let (y_min, y_max) = (frame_params.y_min, frame_params.y_max);
+        let re = sampling.axis(x_min, x_max, width);
+        let im = sampling.axis(y_min, y_max, height);
+        let dx = sampling.spacing(x_min, x_max, width);
+        let dy = sampling.spacing(y_min, y_max, height);
+        let quantum = lattice(dx.min(dy));
+        let config = frame_params.escape_config();
+        let current: Vec<Mutex<HashMap<CacheKey, Escape>>> =
+            (0..CACHE_SHARDS).map(|_| Mutex::default()).collect();
+        let (lookups, hits) = (AtomicU64::new(0), AtomicU64::new(0));
+        let sample = |i: usize, j: usize, (ox, oy): (f64, f64)| {
+            let point = frame_params.rotated(Complex::new(re[j] + ox * dx, im[i] + oy * dy));
+            let point = Complex::new(snap(point.re, quantum), snap(point.im, quantum));
+            let key = (point.re.to_bits(), point.im.to_bits(), config.max_iter);
+            let k = shard(&key);
+            lookups.fetch_add(1, Ordering::Relaxed);
+            let cached = current[k].lock().unwrap().get(&key).copied();
+            let escape = match cached.or_else(|| previous[k].get(&key).copied()) {
+                Some(escape) => {
+                    hits.fetch_add(1, Ordering::Relaxed);
+                    escape
+                }
+                None => frame_params.fractal.escape(&point, &config),
+            };
+            current[k].lock().unwrap().insert(key, escape);
+            (point, escape)
+        };
+        let image = with_threads(params.threads, || {
+            shade_samples(
+                frame_params,
+                frame_params.fractal,
+                (width, height),
+                dx.max(dy),
+                frame_params.samples_per_pixel,
+                sample,
+                &mut |_, _| {},
+            )
+        })?;
+        save_as(&image, &path, OutputFormat::Png)?;
+
+        stats.lookups += lookups.into_inner();
+        stats.hits += hits.into_inner();
+        previous = current
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap())
+            .collect();
+    }
+    Ok(stats)
+}
+
+// The largest power of two no more than half of `spacing`, or 0, for no
+// snapping, when the frame is a single sample wide.
+fn lattice(spacing: f64) -> f64 {
+    if spacing > 0.0 {
+        2f64.powi((spacing / 2.0).log2().floor() as i32)
+    } else {
+        0.0
+    }
+}
+
+// `x` rounded to the nearest multiple of the power of two `quantum`, which is
+// exact, so equal lattice points from different frames have equal bits.
+fn snap(x: f64, quantum: f64) -> f64 {
+    if quantum > 0.0 {
+        (x / quantum).round() * quantum
+    } else {
+        x
+    }
+}